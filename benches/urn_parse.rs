@@ -0,0 +1,34 @@
+//! Compares the time spent parsing URNs with `from_str` and `from_str_fast`.
+//!
+//! Run with `cargo bench --bench urn_parse`.
+
+use std::hint::black_box;
+use std::str::FromStr;
+use std::time::Instant;
+
+use cutoff_common::urn::Urn;
+
+const ITERATIONS: usize = 100_000;
+
+/// Runs `f` `ITERATIONS` times and reports the elapsed time per iteration.
+fn measure<F: FnMut()>(label: &str, mut f: F) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed();
+
+    println!("{label:<16} {:>10.1} ns/iter", elapsed.as_nanos() as f64 / ITERATIONS as f64);
+}
+
+fn main() {
+    let input = "urn:example:some.resource/path/to/item?key=value#section";
+
+    measure("from_str", || {
+        black_box(Urn::from_str(black_box(input)).unwrap());
+    });
+
+    measure("from_str_fast", || {
+        black_box(Urn::from_str_fast(black_box(input), "example").unwrap());
+    });
+}
@@ -0,0 +1,73 @@
+//! Measures the allocations and time spent deriving URNs with `with_query`/`with_fragment`.
+//!
+//! Run with `cargo bench --bench urn_with`. The "owned strings" baseline copies every
+//! component, which is what deriving a URN cost before components were shared.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use cutoff_common::urn::Urn;
+
+/// A global allocator that counts every allocation made through it.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const ITERATIONS: usize = 100_000;
+
+/// Runs `f` `ITERATIONS` times and reports allocations per iteration and elapsed time.
+fn measure<F: FnMut()>(label: &str, mut f: F) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    println!(
+        "{label:<16} {:>6.2} allocations/iter {:>10.1} ns/iter",
+        allocations as f64 / ITERATIONS as f64,
+        elapsed.as_nanos() as f64 / ITERATIONS as f64,
+    );
+}
+
+fn main() {
+    let urn = Urn::from_str("urn:example:some.resource/path/to/item?key=value#section").unwrap();
+
+    measure("shared (Urn)", || {
+        black_box(urn.with_query(Some("other=value")).with_fragment(Some("other")));
+    });
+
+    // Baseline: the cost of copying every component into owned strings, twice. It copies
+    // the whole query, as it was before r- and q-components
+    #[allow(deprecated)]
+    measure("owned strings", || {
+        for _ in 0..2 {
+            black_box((
+                urn.nid().to_string(),
+                urn.nss().to_string(),
+                urn.path().map(String::from),
+                urn.query().map(String::from),
+                urn.fragment().map(String::from),
+            ));
+        }
+    });
+}
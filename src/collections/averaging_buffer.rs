@@ -1,18 +1,122 @@
 //! A buffer that maintains a running average of its elements.
 //!
-//! This module provides the `AveragingBuffer` struct, which is a fixed-capacity
-//! buffer that maintains a running average of the values it contains.
+//! This module provides the `GenericAveragingBuffer` struct, which is a fixed-capacity
+//! buffer that maintains a running average of the values it contains, and the
+//! `AveragingBuffer` alias for its original `usize` flavor.
 
+use std::cmp::Ordering;
 use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::ops::{Add, Sub};
+
+use thiserror::Error;
+
+/// Errors that can occur when constructing an averaging buffer.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AveragingBufferError {
+    /// Returned when a buffer is created with a capacity of zero.
+    #[error("Invalid averaging buffer: capacity must be greater than zero")]
+    ZeroCapacity,
+}
+
+/// A numeric type that can be stored in a [`GenericAveragingBuffer`].
+///
+/// The running sum is kept in the wider `Sum` type, so that adding and removing values
+/// can't overflow: integers are summed in a 128-bit integer of the same signedness, and
+/// floats in an `f64`.
+pub trait AveragingValue: Copy {
+    /// The type the running sum of the values is accumulated in.
+    type Sum: Copy + Debug + Default + Add<Output = Self::Sum> + Sub<Output = Self::Sum>;
+
+    /// Converts the value to the sum type.
+    fn to_sum(self) -> Self::Sum;
+
+    /// Converts the value to an `f64`, possibly losing precision.
+    fn to_f64(self) -> f64;
+
+    /// Converts a sum of values to an `f64`, possibly losing precision.
+    fn sum_to_f64(sum: Self::Sum) -> f64;
+
+    /// Compares two values with a total order, so that sorting can't fail on floats.
+    ///
+    /// Integers use their usual order. Floats use the IEEE 754 total order of
+    /// [`f64::total_cmp`]: `-0.0` comes before `0.0`, a positive NaN after every other
+    /// value, and a negative NaN before every other value.
+    fn total_cmp(&self, other: &Self) -> Ordering;
+
+    /// Checks whether a running sum, once `evicted` has been subtracted from it, may have
+    /// lost the values left in it, and must be recomputed from them.
+    ///
+    /// Integer sums are exact, so this is always `false` for them. A float sum loses the
+    /// small values added to a much larger one, and an infinity or a NaN can't be
+    /// subtracted back out, so this is `true` when `evicted` isn't finite or when it is
+    /// larger than what remains.
+    fn sum_needs_recompute(sum: Self::Sum, evicted: Self) -> bool;
+}
+
+macro_rules! impl_averaging_value {
+    ($sum:ty, $cmp:ident, $needs_recompute:expr => $($value:ty),*) => {
+        $(
+            impl AveragingValue for $value {
+                type Sum = $sum;
+
+                fn to_sum(self) -> $sum { self as $sum }
+
+                fn to_f64(self) -> f64 { self as f64 }
+
+                fn sum_to_f64(sum: $sum) -> f64 { sum as f64 }
+
+                fn total_cmp(&self, other: &Self) -> Ordering { <$value>::$cmp(self, other) }
+
+                fn sum_needs_recompute(sum: $sum, evicted: Self) -> bool {
+                    $needs_recompute(sum, evicted)
+                }
+            }
+        )*
+    };
+}
+
+impl_averaging_value!(u128, cmp, |_, _| false => u8, u16, u32, u64, usize);
+impl_averaging_value!(i128, cmp, |_, _| false => i8, i16, i32, i64, isize);
+impl_averaging_value!(
+    f64,
+    total_cmp,
+    |sum: f64, evicted: Self| !evicted.is_finite() || evicted.to_f64().abs() > sum.abs()
+    => f32, f64
+);
+
+/// The plain state of a [`GenericAveragingBuffer`], to move it around without serialization.
+///
+/// See [`GenericAveragingBuffer::snapshot`] and [`GenericAveragingBuffer::restore`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AveragingBufferState<T = usize> {
+    /// The maximum number of elements the buffer can hold
+    pub capacity: usize,
+    /// The values in the buffer, from the oldest to the newest
+    pub values: Vec<T>,
+}
+
+/// A fixed-capacity buffer of `usize` values that maintains a running average of its elements.
+///
+/// See [`GenericAveragingBuffer`] for the other numeric types.
+pub type AveragingBuffer = GenericAveragingBuffer<usize>;
 
 /// A fixed-capacity buffer that maintains a running average of its elements.
 ///
-/// The `AveragingBuffer` stores a fixed number of `usize` values and provides
-/// methods to add new values and calculate the average of all values currently
-/// in the buffer. When the buffer reaches its capacity, adding a new value will
-/// remove the oldest value.
+/// The buffer stores a fixed number of numeric values and provides methods to add new
+/// values and calculate the average of all values currently in the buffer. When the
+/// buffer reaches its capacity, adding a new value will remove the oldest value.
+///
+/// The sum is accumulated in the value's [`AveragingValue::Sum`] type: a `u128` for
+/// `usize`, so it can't overflow even when the buffer is full of `usize::MAX` values.
+/// Float sums are updated incrementally too, but recomputed from the buffer when an
+/// evicted value isn't finite or outweighs the rest of the sum: a spike, or an infinity,
+/// doesn't corrupt the average once it leaves the window.
+///
+/// Use the [`AveragingBuffer`] alias for `usize` values.
 ///
-/// The buffer uses saturating arithmetic to prevent overflow when calculating the sum.
+/// Every constructor requires a non-zero capacity, which is why there is no `Default`:
+/// a buffer that can't hold a value would never evict one either.
 ///
 /// # Examples
 ///
@@ -33,18 +137,27 @@ use std::collections::VecDeque;
 /// // Add another value, which will push out the oldest value (1)
 /// buffer.push(4);
 /// assert_eq!(buffer.avg(), Some(3.0)); // Average of [2, 3, 4]
+///
+/// // Other numeric types
+/// use cutoff_common::collections::averaging_buffer::GenericAveragingBuffer;
+///
+/// let mut latencies = GenericAveragingBuffer::<f64>::new(2);
+/// latencies.push(0.5);
+/// latencies.push(1.0);
+/// assert_eq!(latencies.avg(), Some(0.75));
 /// ```
-#[derive(Debug, Clone, Default)]
-pub struct AveragingBuffer {
+#[derive(Debug, Clone)]
+pub struct GenericAveragingBuffer<T: AveragingValue> {
     /// The internal buffer storing the values
-    buffer: VecDeque<usize>,
+    buffer: VecDeque<T>,
     /// The maximum number of elements the buffer can hold
     capacity: usize,
-    /// The sum of all elements in the buffer, used for efficient average calculation
-    sum: usize,
+    /// The sum of all elements in the buffer, used for efficient average calculation.
+    /// Wider than the elements so that it can't overflow.
+    sum: T::Sum,
 }
 
-impl AveragingBuffer {
+impl<T: AveragingValue> GenericAveragingBuffer<T> {
     /// Creates a new `AveragingBuffer` with the specified capacity.
     ///
     /// # Parameters
@@ -55,6 +168,11 @@ impl AveragingBuffer {
     ///
     /// A new, empty `AveragingBuffer` with the specified capacity.
     ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero, since such a buffer could never hold a value.
+    /// Use [`GenericAveragingBuffer::try_new`] when the capacity comes from untrusted input.
+    ///
     /// # Examples
     ///
     /// ```
@@ -64,17 +182,80 @@ impl AveragingBuffer {
     /// assert_eq!(buffer.avg(), None); // Empty buffer has no average
     /// ```
     pub fn new(capacity: usize) -> Self {
-        Self {
+        Self::try_new(capacity).expect("AveragingBuffer capacity must be greater than zero")
+    }
+
+    /// Creates a new `AveragingBuffer` with the specified capacity, failing on a zero capacity.
+    ///
+    /// # Parameters
+    ///
+    /// * `capacity` - The maximum number of elements the buffer can hold.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AveragingBuffer)` - A new, empty buffer with the specified capacity.
+    /// * `Err(AveragingBufferError::ZeroCapacity)` - If `capacity` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::{AveragingBuffer, AveragingBufferError};
+    ///
+    /// assert!(AveragingBuffer::try_new(5).is_ok());
+    /// assert_eq!(AveragingBuffer::try_new(0).unwrap_err(), AveragingBufferError::ZeroCapacity);
+    /// ```
+    pub fn try_new(capacity: usize) -> Result<Self, AveragingBufferError> {
+        if capacity == 0 {
+            return Err(AveragingBufferError::ZeroCapacity);
+        }
+        Ok(Self {
             buffer: VecDeque::with_capacity(capacity),
             capacity,
-            sum: 0,
+            sum: T::Sum::default(),
+        })
+    }
+
+    /// Creates a new `AveragingBuffer` pre-seeded with copies of a baseline value.
+    ///
+    /// Seeding avoids a misleading average while the window fills up: the seeded values
+    /// count like any other, and are pushed out by real values as they come in.
+    ///
+    /// # Parameters
+    ///
+    /// * `capacity` - The maximum number of elements the buffer can hold.
+    /// * `fill_value` - The baseline value to seed the buffer with.
+    /// * `fill_count` - The number of copies of `fill_value` to seed, capped at `capacity`.
+    ///
+    /// # Returns
+    ///
+    /// A new `AveragingBuffer` holding `min(fill_count, capacity)` copies of `fill_value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero, like [`GenericAveragingBuffer::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::with_initial(4, 100, 4);
+    /// assert_eq!(buffer.avg(), Some(100.0));
+    ///
+    /// buffer.push(0);
+    /// assert_eq!(buffer.avg(), Some(75.0)); // Average of [100, 100, 100, 0]
+    /// ```
+    pub fn with_initial(capacity: usize, fill_value: T, fill_count: usize) -> Self {
+        let mut buffer = Self::new(capacity);
+        for _ in 0..fill_count.min(capacity) {
+            buffer.push(fill_value);
         }
+        buffer
     }
 
     /// Adds a value to the buffer.
     ///
     /// If the buffer is at capacity, the oldest value will be removed.
-    /// The sum is updated using saturating arithmetic to prevent overflow.
     ///
     /// # Parameters
     ///
@@ -94,16 +275,106 @@ impl AveragingBuffer {
     /// buffer.push(3);
     /// assert_eq!(buffer.avg(), Some(2.5)); // Average of [2, 3]
     /// ```
-    pub fn push(&mut self, value: usize) {
+    pub fn push(&mut self, value: T) {
         if self.buffer.len() == self.capacity {
             if let Some(old) = self.buffer.pop_front() {
-                // Use saturating subtraction to prevent underflow
-                self.sum = self.sum.saturating_sub(old);
+                self.evict(old);
             }
         }
         self.buffer.push_back(value);
-        // Use saturating addition to prevent overflow
-        self.sum = self.sum.saturating_add(value);
+        self.sum = self.sum + value.to_sum();
+    }
+
+    /// Removes a value popped from the buffer from the running sum.
+    fn evict(&mut self, old: T) {
+        self.sum = self.sum - old.to_sum();
+        if T::sum_needs_recompute(self.sum, old) {
+            self.sum = self.buffer.iter().fold(T::Sum::default(), |sum, value| sum + value.to_sum());
+        }
+    }
+
+    /// Changes the capacity of the buffer.
+    ///
+    /// When shrinking below the number of values, the oldest values are dropped, as if
+    /// they had been pushed out. When growing, the values are kept as they are.
+    ///
+    /// # Parameters
+    ///
+    /// * `capacity` - The new maximum number of elements the buffer can hold.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero, like [`GenericAveragingBuffer::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(3);
+    /// buffer.extend([1, 2, 3]);
+    ///
+    /// buffer.set_capacity(2);
+    /// assert_eq!(buffer.len(), 2);
+    /// assert_eq!(buffer.avg(), Some(2.5)); // Average of [2, 3]
+    /// ```
+    pub fn set_capacity(&mut self, capacity: usize) {
+        assert!(capacity > 0, "AveragingBuffer capacity must be greater than zero");
+        while self.buffer.len() > capacity {
+            if let Some(old) = self.buffer.pop_front() {
+                self.evict(old);
+            }
+        }
+        self.capacity = capacity;
+    }
+
+    /// Returns the maximum number of elements the buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of elements in the buffer.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Checks whether the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Returns an iterator over the values in the buffer, from the oldest to the newest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(2);
+    /// buffer.extend([1, 2, 3]);
+    /// assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![2, 3]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.buffer.iter()
+    }
+
+    /// Removes every value from the buffer, keeping its capacity and allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(3);
+    /// buffer.extend([1, 2, 3]);
+    ///
+    /// buffer.clear();
+    /// assert_eq!(buffer.avg(), None);
+    /// assert_eq!(buffer.capacity(), 3);
+    /// ```
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.sum = T::Sum::default();
     }
 
     /// Calculates the average of all values in the buffer.
@@ -130,7 +401,398 @@ impl AveragingBuffer {
         if self.buffer.is_empty() {
             None
         } else {
-            Some(self.sum as f64 / self.buffer.len() as f64)
+            Some(T::sum_to_f64(self.sum) / self.buffer.len() as f64)
+        }
+    }
+
+    /// Calculates the population standard deviation of the values in the buffer.
+    ///
+    /// This makes two passes over the buffer, in O(n) time: one for the average, then one
+    /// summing the squared deviations from it. Unlike a running sum of squares, this stays
+    /// accurate when the values are large compared to their spread.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(f64)` - The population standard deviation of the values in the buffer.
+    /// * `None` - If the buffer is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(8);
+    /// buffer.extend([2, 4, 4, 4, 5, 5, 7, 9]);
+    /// assert_eq!(buffer.std_deviation(), Some(2.0));
+    /// ```
+    pub fn std_deviation(&self) -> Option<f64> {
+        let len = self.buffer.len() as f64;
+        self.squared_deviations_sum().map(|squared_deviations| (squared_deviations / len).sqrt())
+    }
+
+    /// Calculates the sample standard deviation of the values in the buffer, using
+    /// Bessel's correction: the squared deviations are divided by `len - 1`.
+    ///
+    /// Like [`GenericAveragingBuffer::std_deviation`], this makes two passes over the buffer.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(f64)` - The sample standard deviation of the values in the buffer.
+    /// * `None` - If the buffer holds fewer than two values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(3);
+    /// buffer.push(1);
+    /// assert_eq!(buffer.sample_deviation(), None);
+    ///
+    /// buffer.extend([2, 3]);
+    /// assert_eq!(buffer.sample_deviation(), Some(1.0));
+    /// ```
+    pub fn sample_deviation(&self) -> Option<f64> {
+        if self.buffer.len() < 2 {
+            return None;
+        }
+        let len = self.buffer.len() as f64;
+        self.squared_deviations_sum().map(|squared_deviations| (squared_deviations / (len - 1.0)).sqrt())
+    }
+
+    /// Computes the sum of the squared deviations from the average, `Σ(x - avg)²`, with a
+    /// first pass over the buffer for the average and a second one for the deviations.
+    fn squared_deviations_sum(&self) -> Option<f64> {
+        if self.buffer.is_empty() {
+            return None;
+        }
+        let len = self.buffer.len() as f64;
+        let avg = self.buffer.iter().map(|value| value.to_f64()).sum::<f64>() / len;
+        Some(self.buffer.iter().map(|value| (value.to_f64() - avg).powi(2)).sum())
+    }
+
+    /// Reports how full the buffer is, as the number of values over the capacity.
+    ///
+    /// Until the buffer is full, its average is based on fewer samples and less reliable:
+    /// this lets a consumer wait for the window to be warm enough before acting on it.
+    ///
+    /// # Returns
+    ///
+    /// A ratio from `0.0` for an empty buffer to `1.0` for a full one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(4);
+    /// buffer.push(1);
+    /// assert_eq!(buffer.fill_ratio(), 0.25);
+    ///
+    /// buffer.extend([2, 3, 4, 5]);
+    /// assert_eq!(buffer.fill_ratio(), 1.0);
+    /// ```
+    pub fn fill_ratio(&self) -> f64 {
+        (self.buffer.len() as f64 / self.capacity as f64).min(1.0)
+    }
+
+    /// Calculates the pooled average of several buffers, as if all their values were in
+    /// a single buffer: each buffer weighs by its number of values.
+    ///
+    /// # Parameters
+    ///
+    /// * `buffers` - The buffers to pool.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(f64)` - The average of all the values in all the buffers.
+    /// * `None` - If every buffer is empty, or there is none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut a = AveragingBuffer::new(3);
+    /// a.extend([1, 2, 3]);
+    /// let mut b = AveragingBuffer::new(3);
+    /// b.push(10);
+    ///
+    /// // Not the average of the averages, 6.0
+    /// assert_eq!(AveragingBuffer::pooled([&a, &b]), Some(4.0));
+    /// ```
+    pub fn pooled<'a>(buffers: impl IntoIterator<Item = &'a Self>) -> Option<f64>
+    where
+        T: 'a,
+    {
+        let (sum, len) = buffers.into_iter()
+            .fold((T::Sum::default(), 0), |(sum, len), buffer| (sum + buffer.sum, len + buffer.buffer.len()));
+        (len > 0).then(|| T::sum_to_f64(sum) / len as f64)
+    }
+
+    /// Calculates the average of all values in the buffer, weighted by recency.
+    ///
+    /// The values are weighted linearly, oldest to newest: the oldest value has a weight
+    /// of 1, the next one 2, and so on up to the newest value with a weight of `len`.
+    /// The weighted sum is divided by the sum of the weights, `len * (len + 1) / 2`.
+    /// This favors recent samples without the unbounded memory of an exponentially
+    /// weighted average.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(f64)` - The weighted average of all values in the buffer.
+    /// * `None` - If the buffer is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(3);
+    /// buffer.push(3);
+    /// buffer.push(6);
+    /// buffer.push(9);
+    /// assert_eq!(buffer.avg_linear_weighted(), Some(7.0)); // (3*1 + 6*2 + 9*3) / 6
+    /// ```
+    pub fn avg_linear_weighted(&self) -> Option<f64> {
+        if self.buffer.is_empty() {
+            return None;
+        }
+
+        let weighted_sum: f64 = self.buffer.iter()
+            .enumerate()
+            .map(|(i, &value)| (i + 1) as f64 * value.to_f64())
+            .sum();
+        let len = self.buffer.len() as f64;
+        let weights_sum = len * (len + 1.0) / 2.0;
+
+        Some(weighted_sum / weights_sum)
+    }
+
+    /// Checks whether the average of the buffer is strictly above a threshold.
+    ///
+    /// An empty buffer has no average, so it never exceeds the threshold.
+    ///
+    /// # Parameters
+    ///
+    /// * `threshold` - The value the average is compared to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(3);
+    /// assert!(!buffer.avg_exceeds(0.0)); // No data, no alert
+    ///
+    /// buffer.push(10);
+    /// assert!(buffer.avg_exceeds(5.0));
+    /// ```
+    pub fn avg_exceeds(&self, threshold: f64) -> bool {
+        self.avg().is_some_and(|avg| avg > threshold)
+    }
+
+    /// Checks whether the average of the buffer is strictly below a threshold.
+    ///
+    /// An empty buffer has no average, so it is never below the threshold.
+    ///
+    /// # Parameters
+    ///
+    /// * `threshold` - The value the average is compared to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(3);
+    /// assert!(!buffer.avg_below(100.0)); // No data, no alert
+    ///
+    /// buffer.push(10);
+    /// assert!(buffer.avg_below(100.0));
+    /// ```
+    pub fn avg_below(&self, threshold: f64) -> bool {
+        self.avg().is_some_and(|avg| avg < threshold)
+    }
+
+    /// Returns the smallest value in the buffer.
+    ///
+    /// This scans the buffer, in O(n) time. Values are compared with
+    /// [`AveragingValue::total_cmp`], so the result doesn't depend on their order: a
+    /// negative NaN is smaller than any other float, and a positive NaN is never the
+    /// smallest unless every value is a positive NaN.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(T)` - The smallest value in the buffer.
+    /// * `None` - If the buffer is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(3);
+    /// assert_eq!(buffer.min(), None);
+    ///
+    /// buffer.extend([4, 1, 7]);
+    /// assert_eq!(buffer.min(), Some(1));
+    /// ```
+    pub fn min(&self) -> Option<T> {
+        self.buffer.iter().copied().min_by(T::total_cmp)
+    }
+
+    /// Returns the largest value in the buffer.
+    ///
+    /// Like [`GenericAveragingBuffer::min`], this scans the buffer, in O(n) time, and
+    /// compares with [`AveragingValue::total_cmp`]: a positive NaN is larger than any other
+    /// float.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(T)` - The largest value in the buffer.
+    /// * `None` - If the buffer is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(3);
+    /// buffer.extend([4, 1, 7]);
+    /// assert_eq!(buffer.max(), Some(7));
+    /// ```
+    pub fn max(&self) -> Option<T> {
+        self.buffer.iter().copied().max_by(T::total_cmp)
+    }
+
+    /// Calculates the median of the values in the buffer.
+    ///
+    /// This copies and sorts the values, in O(n log n) time. For an even number of values,
+    /// the median is the average of the two middle ones. The values are sorted with
+    /// [`AveragingValue::total_cmp`], so float NaNs are sorted too, before or after every
+    /// other value depending on their sign: the median is NaN only if a middle value is.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(f64)` - The median of the values in the buffer.
+    /// * `None` - If the buffer is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(4);
+    /// buffer.extend([9, 1, 4]);
+    /// assert_eq!(buffer.median(), Some(4.0));
+    ///
+    /// buffer.push(2);
+    /// assert_eq!(buffer.median(), Some(3.0)); // Average of 2 and 4
+    /// ```
+    pub fn median(&self) -> Option<f64> {
+        if self.buffer.is_empty() {
+            return None;
+        }
+
+        let mut values: Vec<T> = self.buffer.iter().copied().collect();
+        values.sort_by(T::total_cmp);
+        let middle = values.len() / 2;
+        if values.len() % 2 == 1 {
+            Some(values[middle].to_f64())
+        } else {
+            Some((values[middle - 1].to_f64() + values[middle].to_f64()) / 2.0)
+        }
+    }
+
+    /// Counts the distinct values in the buffer.
+    ///
+    /// Compared to [`GenericAveragingBuffer::len`], this tells how varied the values are.
+    /// Like [`GenericAveragingBuffer::median`], this copies and sorts the values with
+    /// [`AveragingValue::total_cmp`], in O(n log n) time, so that floats can be counted
+    /// too. Values are then compared with `==`: `-0.0` and `0.0` count once, while values
+    /// that aren't equal to themselves, like a float NaN, are each counted as distinct.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(5);
+    /// buffer.extend([3, 1, 3, 3, 1]);
+    /// assert_eq!(buffer.distinct_count(), 2);
+    /// assert_eq!(buffer.len(), 5);
+    /// ```
+    pub fn distinct_count(&self) -> usize
+    where
+        T: PartialEq,
+    {
+        let mut values: Vec<T> = self.buffer.iter().copied().collect();
+        values.sort_by(T::total_cmp);
+        values.dedup_by(|a, b| a == b);
+        values.len()
+    }
+
+    /// Captures the state of the buffer, to be restored with [`GenericAveragingBuffer::restore`].
+    ///
+    /// This is meant for in-process hot reload, where the state crosses a reload
+    /// boundary as a plain struct, without any serialization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::averaging_buffer::AveragingBuffer;
+    ///
+    /// let mut buffer = AveragingBuffer::new(3);
+    /// buffer.extend([1, 2, 3]);
+    ///
+    /// let restored = AveragingBuffer::restore(buffer.snapshot()).unwrap();
+    /// assert_eq!(restored.avg(), Some(2.0));
+    /// ```
+    pub fn snapshot(&self) -> AveragingBufferState<T> {
+        AveragingBufferState {
+            capacity: self.capacity,
+            values: self.buffer.iter().copied().collect(),
+        }
+    }
+
+    /// Recreates a buffer from a state captured with [`GenericAveragingBuffer::snapshot`].
+    ///
+    /// The state may also be assembled by hand: if it holds more values than its
+    /// capacity, only the newest ones are kept, as if they had been pushed in order.
+    ///
+    /// # Parameters
+    ///
+    /// * `state` - The state to restore.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AveragingBuffer)` - The restored buffer.
+    /// * `Err(AveragingBufferError::ZeroCapacity)` - If the state's capacity is zero.
+    pub fn restore(state: AveragingBufferState<T>) -> Result<Self, AveragingBufferError> {
+        let mut buffer = Self::try_new(state.capacity)?;
+        buffer.extend(state.values);
+        Ok(buffer)
+    }
+}
+
+impl<T: AveragingValue> Extend<T> for GenericAveragingBuffer<T> {
+    /// Pushes every value of the iterator into the buffer.
+    ///
+    /// The result is the same as calling `push` for each value: only the last `capacity`
+    /// values are retained. Values that would be evicted right away are skipped without
+    /// ever touching the buffer or its sum.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut retained = VecDeque::with_capacity(self.capacity);
+        for value in iter {
+            if retained.len() == self.capacity {
+                retained.pop_front();
+            }
+            retained.push_back(value);
+        }
+
+        for value in retained {
+            self.push(value);
         }
     }
 }
@@ -147,6 +809,37 @@ mod tests {
         assert_eq!(buffer.sum, 0);
     }
 
+    #[test]
+    #[should_panic(expected = "capacity must be greater than zero")]
+    fn test_new_zero_capacity() {
+        AveragingBuffer::new(0);
+    }
+
+    #[test]
+    fn test_every_constructor_evicts_past_capacity() {
+        let buffers = [
+            AveragingBuffer::new(3),
+            AveragingBuffer::try_new(3).unwrap(),
+            AveragingBuffer::with_initial(3, 7, 2),
+            AveragingBuffer::restore(AveragingBufferState { capacity: 3, values: vec![7] }).unwrap(),
+        ];
+        for mut buffer in buffers {
+            for value in 0..1000 {
+                buffer.push(value);
+            }
+            assert_eq!(buffer.len(), 3);
+            assert_eq!(buffer.avg(), Some(998.0)); // Average of [997, 998, 999]
+        }
+    }
+
+    #[test]
+    fn test_try_new() {
+        assert_eq!(AveragingBuffer::try_new(0).unwrap_err(), AveragingBufferError::ZeroCapacity);
+
+        let buffer = AveragingBuffer::try_new(3).unwrap();
+        assert_eq!(buffer.capacity, 3);
+    }
+
     #[test]
     fn test_push_and_avg() {
         let mut buffer = AveragingBuffer::new(3);
@@ -172,9 +865,393 @@ mod tests {
         buffer.push(usize::MAX);
         buffer.push(usize::MAX);
         buffer.push(usize::MAX);
-        assert_eq!(buffer.sum, usize::MAX);
+        assert_eq!(buffer.sum, 3 * usize::MAX as u128);
+        assert_eq!(buffer.avg(), Some(usize::MAX as f64));
         buffer.push(1);
-        assert_eq!(buffer.sum, 1);
-        assert!((buffer.avg().unwrap() - 0.3333333333333333).abs() < 1e-10);
+        assert_eq!(buffer.sum, 2 * usize::MAX as u128 + 1);
+        assert_eq!(buffer.avg(), Some((2 * usize::MAX as u128 + 1) as f64 / 3.0));
+    }
+
+    #[test]
+    fn test_deviations() {
+        let mut buffer = AveragingBuffer::new(4);
+        assert_eq!(buffer.std_deviation(), None);
+        assert_eq!(buffer.sample_deviation(), None);
+
+        buffer.push(5);
+        assert_eq!(buffer.std_deviation(), Some(0.0));
+        assert_eq!(buffer.sample_deviation(), None);
+
+        buffer.extend([1, 3, 5, 7]);
+        // [1, 3, 5, 7]: the squared deviations add up to 20
+        assert_eq!(buffer.std_deviation(), Some(5.0f64.sqrt()));
+        assert_eq!(buffer.sample_deviation(), Some((20.0f64 / 3.0).sqrt()));
+
+        // Evicted values no longer count
+        buffer.extend([4, 4, 4, 4]);
+        assert_eq!(buffer.std_deviation(), Some(0.0));
+    }
+
+    #[test]
+    fn test_deviations_after_evicting_a_large_value() {
+        let mut buffer = AveragingBuffer::new(2);
+        buffer.extend([10_000_000_000, 1, 2]);
+        assert_eq!(buffer.std_deviation(), Some(0.5));
+        assert_eq!(buffer.sample_deviation(), Some(0.5f64.sqrt()));
+
+        // Large values with a small spread
+        let mut buffer = GenericAveragingBuffer::<f64>::new(3);
+        buffer.extend([1e9 + 1.0, 1e9 + 2.0, 1e9 + 3.0]);
+        assert_eq!(buffer.sample_deviation(), Some(1.0));
+    }
+
+    #[test]
+    fn test_deviations_signed_and_float() {
+        let mut buffer = GenericAveragingBuffer::<i64>::new(2);
+        buffer.extend([-3, 3]);
+        assert_eq!(buffer.std_deviation(), Some(3.0));
+
+        let mut buffer = GenericAveragingBuffer::<f64>::new(2);
+        buffer.extend([0.5, 1.5]);
+        assert_eq!(buffer.std_deviation(), Some(0.5));
+        assert_eq!(buffer.sample_deviation(), Some(0.5f64.sqrt()));
+    }
+
+    #[test]
+    fn test_set_capacity() {
+        let mut buffer = AveragingBuffer::new(4);
+        buffer.extend([1, 2, 3, 4]);
+
+        buffer.set_capacity(2);
+        assert_eq!(buffer.capacity(), 2);
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.avg(), Some(3.5)); // Average of [3, 4]
+        assert_eq!(buffer.sum, 7);
+        assert_eq!(buffer.std_deviation(), Some(0.5));
+
+        // Growing keeps the values, and lets more in
+        buffer.set_capacity(3);
+        assert_eq!(buffer.len(), 2);
+        buffer.push(5);
+        assert_eq!(buffer.avg(), Some(4.0)); // Average of [3, 4, 5]
+        buffer.push(6);
+        assert_eq!(buffer.avg(), Some(5.0)); // Average of [4, 5, 6]
+        assert_eq!(buffer.fill_ratio(), 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be greater than zero")]
+    fn test_set_capacity_zero() {
+        AveragingBuffer::new(2).set_capacity(0);
+    }
+
+    #[test]
+    fn test_iter_and_clear() {
+        let mut buffer = AveragingBuffer::new(3);
+        buffer.extend([1, 2, 3, 4]);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+
+        let allocated = buffer.buffer.capacity();
+        buffer.clear();
+        assert_eq!(buffer.avg(), None);
+        assert_eq!(buffer.len(), 0);
+        assert_eq!(buffer.iter().count(), 0);
+        assert_eq!(buffer.sum, 0);
+        assert_eq!(buffer.capacity(), 3);
+        assert_eq!(buffer.buffer.capacity(), allocated);
+
+        // The next measurement phase starts from scratch
+        buffer.push(10);
+        assert_eq!(buffer.avg(), Some(10.0));
+        assert_eq!(buffer.std_deviation(), Some(0.0));
+    }
+
+    #[test]
+    fn test_fill_ratio() {
+        let mut buffer = AveragingBuffer::new(4);
+        assert_eq!(buffer.fill_ratio(), 0.0);
+
+        for (value, expected) in [(1, 0.25), (2, 0.5), (3, 0.75), (4, 1.0), (5, 1.0)] {
+            buffer.push(value);
+            assert_eq!(buffer.fill_ratio(), expected);
+        }
+    }
+
+    #[test]
+    fn test_f64_values() {
+        let mut buffer = GenericAveragingBuffer::<f64>::new(2);
+        buffer.push(1.5);
+        buffer.push(2.5);
+        assert_eq!(buffer.avg(), Some(2.0));
+
+        buffer.push(-0.5);
+        assert_eq!(buffer.avg(), Some(1.0)); // Average of [2.5, -0.5]
+        assert!(buffer.avg_exceeds(0.5));
+    }
+
+    #[test]
+    fn test_f64_sum_recovers_from_evicted_values() {
+        // The 1.0 is lost when added to 1e20, and must be back once 1e20 is evicted
+        let mut buffer = GenericAveragingBuffer::<f64>::new(2);
+        buffer.extend([1e20, 1.0]);
+        buffer.push(3.0);
+        assert_eq!(buffer.avg(), Some(2.0));
+
+        // `inf - inf` is NaN, which nothing would ever subtract back out
+        let mut buffer = GenericAveragingBuffer::<f64>::new(1);
+        buffer.push(f64::INFINITY);
+        assert_eq!(buffer.avg(), Some(f64::INFINITY));
+        buffer.push(1.0);
+        assert_eq!(buffer.avg(), Some(1.0));
+        buffer.push(f64::NAN);
+        buffer.push(2.0);
+        assert_eq!(buffer.avg(), Some(2.0));
+
+        // Shrinking evicts too
+        let mut buffer = GenericAveragingBuffer::<f64>::new(3);
+        buffer.extend([f64::NEG_INFINITY, 1.0, 3.0]);
+        buffer.set_capacity(2);
+        assert_eq!(buffer.avg(), Some(2.0));
+    }
+
+    #[test]
+    fn test_i64_values() {
+        let mut buffer = GenericAveragingBuffer::<i64>::new(3);
+        buffer.extend([-4, 1, -3]);
+        assert_eq!(buffer.avg(), Some(-2.0));
+        assert_eq!(buffer.sum, -6);
+
+        buffer.push(i64::MIN);
+        buffer.push(i64::MIN);
+        assert_eq!(buffer.sum, 2 * i64::MIN as i128 - 3);
+        assert!(buffer.avg_below(0.0));
+
+        let state = buffer.snapshot();
+        assert_eq!(state.values, vec![-3, i64::MIN, i64::MIN]);
+        assert_eq!(GenericAveragingBuffer::restore(state).unwrap().avg(), buffer.avg());
+    }
+
+    #[test]
+    fn test_pooled() {
+        let mut small = AveragingBuffer::new(5);
+        small.extend([10, 20]);
+        let mut medium = AveragingBuffer::new(3);
+        medium.extend([1, 2, 3, 4]); // [2, 3, 4]
+        let mut large = AveragingBuffer::new(5);
+        large.extend([5, 5, 5, 5, 5]);
+
+        // (30 + 9 + 25) / (2 + 3 + 5)
+        assert_eq!(AveragingBuffer::pooled([&small, &medium, &large]), Some(6.4));
+        assert_eq!(AveragingBuffer::pooled(vec![&small]), small.avg());
+
+        // Empty buffers don't count
+        let empty = AveragingBuffer::new(2);
+        assert_eq!(AveragingBuffer::pooled([&small, &empty]), Some(15.0));
+        assert_eq!(AveragingBuffer::pooled([&empty]), None);
+        assert_eq!(AveragingBuffer::pooled([]), None);
+
+        let mut signed = GenericAveragingBuffer::<i64>::new(2);
+        signed.extend([-4, 2]);
+        assert_eq!(GenericAveragingBuffer::pooled(&[signed.clone(), signed]), Some(-1.0));
+    }
+
+    #[test]
+    fn test_avg_linear_weighted() {
+        let mut buffer = AveragingBuffer::new(4);
+        assert_eq!(buffer.avg_linear_weighted(), None);
+
+        buffer.push(5);
+        assert_eq!(buffer.avg_linear_weighted(), Some(5.0));
+
+        // A ramp weighs towards the most recent values
+        let mut buffer = AveragingBuffer::new(4);
+        for value in [1, 2, 3, 4] {
+            buffer.push(value);
+        }
+        assert_eq!(buffer.avg(), Some(2.5));
+        assert_eq!(buffer.avg_linear_weighted(), Some(3.0)); // (1 + 4 + 9 + 16) / 10
+        assert!(buffer.avg_linear_weighted().unwrap() > buffer.avg().unwrap());
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut buffer = AveragingBuffer::new(10);
+        buffer.extend(0..1000);
+
+        assert_eq!(buffer.buffer, (990..1000).collect::<VecDeque<_>>());
+        assert_eq!(buffer.sum, (990..1000).sum::<u128>());
+        assert_eq!(buffer.avg(), Some(994.5));
+    }
+
+    #[test]
+    fn test_extend_matches_push() {
+        let mut extended = AveragingBuffer::new(3);
+        extended.push(100);
+        extended.extend([1, 2]);
+
+        let mut pushed = AveragingBuffer::new(3);
+        for value in [100, 1, 2] {
+            pushed.push(value);
+        }
+
+        assert_eq!(extended.buffer, pushed.buffer);
+        assert_eq!(extended.sum, pushed.sum);
+    }
+
+    #[test]
+    fn test_avg_thresholds() {
+        let mut buffer = AveragingBuffer::new(3);
+        assert!(!buffer.avg_exceeds(0.0));
+        assert!(!buffer.avg_below(0.0));
+
+        buffer.push(10);
+        buffer.push(20);
+        assert!(buffer.avg_exceeds(14.0));
+        assert!(!buffer.avg_below(14.0));
+
+        assert!(buffer.avg_below(16.0));
+        assert!(!buffer.avg_exceeds(16.0));
+
+        // Both comparisons are strict
+        assert!(!buffer.avg_exceeds(15.0));
+        assert!(!buffer.avg_below(15.0));
+    }
+
+    #[test]
+    fn test_with_initial() {
+        let mut buffer = AveragingBuffer::with_initial(3, 10, 3);
+        assert_eq!(buffer.avg(), Some(10.0));
+
+        buffer.push(40);
+        assert_eq!(buffer.avg(), Some(20.0)); // Average of [10, 10, 40]
+
+        // The fill count is capped at the capacity
+        let buffer = AveragingBuffer::with_initial(2, 7, 10);
+        assert_eq!(buffer.avg(), Some(7.0));
+        assert_eq!(buffer.buffer.len(), 2);
+
+        // Partial seeding
+        let mut buffer = AveragingBuffer::with_initial(4, 8, 1);
+        assert_eq!(buffer.avg(), Some(8.0));
+        buffer.push(2);
+        assert_eq!(buffer.avg(), Some(5.0));
+
+        let buffer = AveragingBuffer::with_initial(4, 8, 0);
+        assert_eq!(buffer.avg(), None);
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut buffer = AveragingBuffer::new(3);
+        buffer.extend([5, 1, 2, 6]);
+
+        let state = buffer.snapshot();
+        assert_eq!(state, AveragingBufferState { capacity: 3, values: vec![1, 2, 6] });
+
+        let mut restored = AveragingBuffer::restore(state).unwrap();
+        assert_eq!(restored.avg(), buffer.avg());
+        assert_eq!(restored.avg_linear_weighted(), buffer.avg_linear_weighted());
+
+        // The restored buffer keeps evicting in the same order
+        buffer.push(9);
+        restored.push(9);
+        assert_eq!(restored.avg(), buffer.avg());
+    }
+
+    #[test]
+    fn test_restore_invalid_state() {
+        let state = AveragingBufferState { capacity: 0, values: vec![] };
+        assert_eq!(AveragingBuffer::restore(state).unwrap_err(), AveragingBufferError::ZeroCapacity);
+
+        // Extra values are dropped from the oldest
+        let state = AveragingBufferState { capacity: 2, values: vec![100, 1, 3] };
+        assert_eq!(AveragingBuffer::restore(state).unwrap().avg(), Some(2.0));
+    }
+
+    #[test]
+    fn test_min_max_median() {
+        let mut buffer = AveragingBuffer::new(4);
+        assert_eq!(buffer.min(), None);
+        assert_eq!(buffer.max(), None);
+        assert_eq!(buffer.median(), None);
+
+        buffer.push(5);
+        assert_eq!((buffer.min(), buffer.max(), buffer.median()), (Some(5), Some(5), Some(5.0)));
+
+        buffer.extend([8, 2, 6]);
+        assert_eq!((buffer.min(), buffer.max()), (Some(2), Some(8)));
+        assert_eq!(buffer.median(), Some(5.5)); // Average of 5 and 6
+
+        // Evicted values no longer count
+        buffer.push(3);
+        assert_eq!((buffer.min(), buffer.max()), (Some(2), Some(8)));
+        assert_eq!(buffer.median(), Some(4.5)); // [8, 2, 6, 3]
+        buffer.extend([7, 7]);
+        assert_eq!((buffer.min(), buffer.max()), (Some(3), Some(7)));
+        assert_eq!(buffer.median(), Some(6.5)); // [6, 3, 7, 7]
+    }
+
+    #[test]
+    fn test_min_max_median_signed_and_float() {
+        let mut buffer = GenericAveragingBuffer::<i64>::new(3);
+        buffer.extend([-5, 10, -1]);
+        assert_eq!((buffer.min(), buffer.max(), buffer.median()), (Some(-5), Some(10), Some(-1.0)));
+
+        let mut buffer = GenericAveragingBuffer::<f64>::new(2);
+        buffer.extend([2.5, 0.5]);
+        assert_eq!((buffer.min(), buffer.max(), buffer.median()), (Some(0.5), Some(2.5), Some(1.5)));
+    }
+
+    #[test]
+    fn test_min_max_median_nan() {
+        let nan = f64::NAN;
+        let mut buffer = GenericAveragingBuffer::<f64>::new(64);
+        buffer.extend((0..64).map(|i| if i % 5 == 0 { nan } else { i as f64 }));
+        assert_eq!(buffer.min(), Some(1.0));
+        assert!(buffer.max().unwrap().is_nan());
+        // 13 NaNs sort last, leaving 39.0 and 41.0 in the middle
+        assert_eq!(buffer.median(), Some(40.0));
+
+        // The insertion order doesn't matter
+        for values in [[nan, 2.0, 1.0], [2.0, nan, 1.0], [1.0, 2.0, nan]] {
+            let mut buffer = GenericAveragingBuffer::<f64>::new(3);
+            buffer.extend(values);
+            assert_eq!(buffer.min(), Some(1.0));
+            assert!(buffer.max().unwrap().is_nan());
+            assert_eq!(buffer.median(), Some(2.0));
+        }
+
+        // A negative NaN sorts first
+        let mut buffer = GenericAveragingBuffer::<f64>::new(3);
+        buffer.extend([2.0, -nan, 1.0]);
+        assert!(buffer.min().unwrap().is_nan());
+        assert_eq!(buffer.max(), Some(2.0));
+        assert_eq!(buffer.median(), Some(1.0));
+    }
+
+    #[test]
+    fn test_distinct_count() {
+        let mut buffer = AveragingBuffer::new(6);
+        assert_eq!(buffer.distinct_count(), 0);
+
+        buffer.extend([4, 2, 4, 4, 9, 2]);
+        assert_eq!(buffer.distinct_count(), 3);
+        assert!(buffer.distinct_count() < buffer.len());
+
+        // Only the current contents count
+        buffer.extend([7, 8, 9]);
+        assert_eq!(buffer.distinct_count(), 5); // [4, 9, 2, 7, 8, 9]
+
+        let mut buffer = GenericAveragingBuffer::<f64>::new(4);
+        buffer.extend([0.5, -1.0, 0.5, 0.5]);
+        assert_eq!(buffer.distinct_count(), 2);
+
+        // Each NaN is distinct, while both zeros are equal
+        let mut buffer = GenericAveragingBuffer::<f64>::new(64);
+        buffer.extend((0..64).map(|i| if i % 4 == 0 { f64::NAN } else { (i % 3) as f64 }));
+        assert_eq!(buffer.distinct_count(), 16 + 3);
+        // The oldest NaN is evicted, two more come in, and the zeros count as one value
+        buffer.extend([0.0, -0.0, f64::NAN, -f64::NAN]);
+        assert_eq!(buffer.distinct_count(), 17 + 3);
     }
 }
@@ -0,0 +1,161 @@
+//! An exponentially weighted moving average.
+//!
+//! This module provides the `EwmaBuffer` struct, a sibling of `AveragingBuffer` that
+//! reacts faster to recent values and needs no window.
+
+use thiserror::Error;
+
+/// Errors that can occur when constructing an exponentially weighted moving average.
+#[derive(Error, Debug, PartialEq)]
+pub enum EwmaBufferError {
+    /// Returned when the smoothing factor isn't in `(0.0, 1.0]`.
+    #[error("Invalid EWMA buffer: alpha must be in (0, 1], got {0}")]
+    InvalidAlpha(f64),
+}
+
+/// An exponentially weighted moving average (EWMA) of `f64` values.
+///
+/// Each pushed value updates the estimate as `alpha * value + (1 - alpha) * estimate`, the
+/// first value becoming the estimate as is. A high `alpha` follows the latest values
+/// closely, while a low `alpha` smooths spikes out but lags behind. Unlike an
+/// [`AveragingBuffer`](super::averaging_buffer::AveragingBuffer), every past value keeps
+/// a decreasing weight, and only the estimate is stored.
+///
+/// Non-finite values are ignored, as they would otherwise poison the estimate for good.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::collections::ewma_buffer::EwmaBuffer;
+///
+/// let mut ewma = EwmaBuffer::new(0.5).unwrap();
+/// assert_eq!(ewma.value(), None);
+///
+/// ewma.push(10.0);
+/// assert_eq!(ewma.value(), Some(10.0));
+///
+/// ewma.push(20.0);
+/// assert_eq!(ewma.value(), Some(15.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct EwmaBuffer {
+    /// The smoothing factor, in `(0.0, 1.0]`
+    alpha: f64,
+    /// The current estimate, if any value was pushed
+    value: Option<f64>,
+}
+
+impl EwmaBuffer {
+    /// Creates a new `EwmaBuffer` with the given smoothing factor.
+    ///
+    /// # Parameters
+    ///
+    /// * `alpha` - The weight of each new value, in `(0.0, 1.0]`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(EwmaBuffer)` - A new buffer without any estimate.
+    /// * `Err(EwmaBufferError::InvalidAlpha)` - If `alpha` isn't in `(0.0, 1.0]`, or is `NaN`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::ewma_buffer::{EwmaBuffer, EwmaBufferError};
+    ///
+    /// assert!(EwmaBuffer::new(1.0).is_ok());
+    /// assert_eq!(EwmaBuffer::new(0.0).unwrap_err(), EwmaBufferError::InvalidAlpha(0.0));
+    /// ```
+    pub fn new(alpha: f64) -> Result<Self, EwmaBufferError> {
+        if !(alpha > 0.0 && alpha <= 1.0) {
+            return Err(EwmaBufferError::InvalidAlpha(alpha));
+        }
+        Ok(Self { alpha, value: None })
+    }
+
+    /// Updates the estimate with a new value.
+    ///
+    /// # Parameters
+    ///
+    /// * `value` - The value to add. Non-finite values are ignored.
+    pub fn push(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        self.value = Some(match self.value {
+            Some(current) => self.alpha * value + (1.0 - self.alpha) * current,
+            None => value,
+        });
+    }
+
+    /// Returns the current estimate.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(f64)` - The exponentially weighted moving average of the pushed values.
+    /// * `None` - If no value was pushed yet.
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+
+    /// Returns the smoothing factor.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Forgets the current estimate, keeping the smoothing factor.
+    pub fn reset(&mut self) {
+        self.value = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_alpha() {
+        for alpha in [0.0, -0.5, 1.01, f64::NAN, f64::INFINITY] {
+            assert!(matches!(EwmaBuffer::new(alpha), Err(EwmaBufferError::InvalidAlpha(_))), "{}", alpha);
+        }
+        assert!(EwmaBuffer::new(f64::MIN_POSITIVE).is_ok());
+    }
+
+    #[test]
+    fn test_alpha_one_tracks_latest() {
+        let mut ewma = EwmaBuffer::new(1.0).unwrap();
+        for value in [3.0, -7.5, 100.0, 0.25] {
+            ewma.push(value);
+            assert_eq!(ewma.value(), Some(value));
+        }
+    }
+
+    #[test]
+    fn test_small_alpha_lags() {
+        let mut ewma = EwmaBuffer::new(0.1).unwrap();
+        ewma.push(0.0);
+        ewma.push(100.0);
+        assert!((ewma.value().unwrap() - 10.0).abs() < 1e-9);
+
+        // It takes many values to catch up with a step
+        for _ in 0..20 {
+            ewma.push(100.0);
+        }
+        let value = ewma.value().unwrap();
+        assert!(value > 80.0 && value < 100.0, "{}", value);
+    }
+
+    #[test]
+    fn test_ignores_non_finite_and_reset() {
+        let mut ewma = EwmaBuffer::new(0.5).unwrap();
+        ewma.push(f64::NAN);
+        assert_eq!(ewma.value(), None);
+
+        ewma.push(4.0);
+        ewma.push(f64::INFINITY);
+        assert_eq!(ewma.value(), Some(4.0));
+
+        ewma.reset();
+        assert_eq!(ewma.value(), None);
+        assert_eq!(ewma.alpha(), 0.5);
+    }
+}
@@ -0,0 +1,231 @@
+//! A histogram counting values into buckets.
+//!
+//! This module provides the `Histogram` struct, which records `f64` values and counts
+//! them into buckets defined either by a fixed width or by explicit boundaries.
+
+use thiserror::Error;
+
+/// Errors that can occur when constructing a histogram.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum HistogramError {
+    /// Returned when fewer than two boundaries are given, which can't delimit a bucket.
+    #[error("Invalid histogram: at least two boundaries are required")]
+    TooFewBoundaries,
+    /// Returned when the boundaries aren't finite and strictly increasing.
+    #[error("Invalid histogram: boundaries must be finite and strictly increasing")]
+    InvalidBoundaries,
+}
+
+/// A histogram counting `f64` values into buckets.
+///
+/// The buckets are delimited by a sorted list of boundaries: with boundaries
+/// `[b0, b1, ..., bn]`, bucket `i` counts the values `v` such that `b[i] <= v < b[i + 1]`.
+/// A value lying exactly on a boundary therefore goes into the bucket starting there.
+/// Values below `b0` or at or above `bn` aren't in any bucket, and are reported by
+/// `underflow` and `overflow` instead. `NaN` values are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::collections::histogram::Histogram;
+///
+/// // Four buckets of width 10, from 0 to 40
+/// let mut histogram = Histogram::fixed_width(0.0, 10.0, 4).unwrap();
+/// histogram.record(5.0);
+/// histogram.record(10.0);
+/// histogram.record(12.5);
+/// histogram.record(45.0);
+///
+/// assert_eq!(histogram.bucket_counts(), [1, 2, 0, 0]);
+/// assert_eq!(histogram.overflow(), 1);
+/// assert_eq!(histogram.count(), 4);
+/// assert_eq!(histogram.total(), 72.5);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// The boundaries of the buckets, finite and strictly increasing
+    boundaries: Vec<f64>,
+    /// The number of values in each bucket
+    counts: Vec<usize>,
+    /// The number of values below the first boundary
+    underflow: usize,
+    /// The number of values at or above the last boundary
+    overflow: usize,
+    /// The sum of all recorded values
+    total: f64,
+}
+
+impl Histogram {
+    /// Creates a histogram with `buckets` buckets of the same width.
+    ///
+    /// # Parameters
+    ///
+    /// * `start` - The lower boundary of the first bucket.
+    /// * `width` - The width of each bucket.
+    /// * `buckets` - The number of buckets.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Histogram)` - A new, empty histogram.
+    /// * `Err(HistogramError::TooFewBoundaries)` - If `buckets` is zero.
+    /// * `Err(HistogramError::InvalidBoundaries)` - If `start` isn't finite, or `width`
+    ///   isn't finite and positive.
+    pub fn fixed_width(start: f64, width: f64, buckets: usize) -> Result<Self, HistogramError> {
+        if !width.is_finite() || width <= 0.0 {
+            return Err(HistogramError::InvalidBoundaries);
+        }
+        let boundaries = (0..=buckets).map(|i| start + width * i as f64).collect();
+        Self::with_boundaries(boundaries)
+    }
+
+    /// Creates a histogram with explicit bucket boundaries.
+    ///
+    /// # Parameters
+    ///
+    /// * `boundaries` - The boundaries of the buckets, finite and strictly increasing.
+    ///   `n + 1` boundaries delimit `n` buckets.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Histogram)` - A new, empty histogram.
+    /// * `Err(HistogramError::TooFewBoundaries)` - If fewer than two boundaries are given.
+    /// * `Err(HistogramError::InvalidBoundaries)` - If the boundaries aren't finite and
+    ///   strictly increasing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::histogram::Histogram;
+    ///
+    /// let mut histogram = Histogram::with_boundaries(vec![0.0, 1.0, 10.0, 100.0]).unwrap();
+    /// histogram.record(0.5);
+    /// histogram.record(50.0);
+    /// assert_eq!(histogram.bucket_counts(), [1, 0, 1]);
+    /// ```
+    pub fn with_boundaries(boundaries: Vec<f64>) -> Result<Self, HistogramError> {
+        if boundaries.len() < 2 {
+            return Err(HistogramError::TooFewBoundaries);
+        }
+        let valid = boundaries.iter().all(|b| b.is_finite())
+            && boundaries.windows(2).all(|pair| pair[0] < pair[1]);
+        if !valid {
+            return Err(HistogramError::InvalidBoundaries);
+        }
+        Ok(Self {
+            counts: vec![0; boundaries.len() - 1],
+            boundaries,
+            underflow: 0,
+            overflow: 0,
+            total: 0.0,
+        })
+    }
+
+    /// Records a value, counting it in its bucket. `NaN` values are ignored.
+    ///
+    /// # Parameters
+    ///
+    /// * `value` - The value to record.
+    pub fn record(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        self.total += value;
+
+        // The number of boundaries lower than or equal to the value
+        match self.boundaries.partition_point(|b| *b <= value) {
+            0 => self.underflow += 1,
+            n if n == self.boundaries.len() => self.overflow += 1,
+            n => self.counts[n - 1] += 1,
+        }
+    }
+
+    /// Returns the number of values recorded, including those outside of the buckets.
+    pub fn count(&self) -> usize {
+        self.counts.iter().sum::<usize>() + self.underflow + self.overflow
+    }
+
+    /// Returns the sum of the values recorded, including those outside of the buckets.
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+
+    /// Returns the number of values in each bucket, in increasing order of the buckets.
+    pub fn bucket_counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    /// Returns the boundaries of the buckets.
+    pub fn boundaries(&self) -> &[f64] {
+        &self.boundaries
+    }
+
+    /// Returns the number of values recorded below the first boundary.
+    pub fn underflow(&self) -> usize {
+        self.underflow
+    }
+
+    /// Returns the number of values recorded at or above the last boundary.
+    pub fn overflow(&self) -> usize {
+        self.overflow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_width() {
+        let mut histogram = Histogram::fixed_width(0.0, 10.0, 3).unwrap();
+        assert_eq!(histogram.boundaries(), [0.0, 10.0, 20.0, 30.0]);
+
+        for value in [0.0, 9.99, 10.0, 15.0, 29.99, 30.0, -0.01] {
+            histogram.record(value);
+        }
+
+        // Values on an edge go into the bucket starting there
+        assert_eq!(histogram.bucket_counts(), [2, 2, 1]);
+        assert_eq!(histogram.underflow(), 1);
+        assert_eq!(histogram.overflow(), 1);
+        assert_eq!(histogram.count(), 7);
+        assert!((histogram.total() - 94.97).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_explicit_boundaries() {
+        let mut histogram = Histogram::with_boundaries(vec![-1.0, 0.0, 1.0, 10.0]).unwrap();
+
+        for value in [-1.0, -0.5, 0.0, 1.0, 9.0, 10.0, f64::NAN] {
+            histogram.record(value);
+        }
+
+        assert_eq!(histogram.bucket_counts(), [2, 1, 2]);
+        assert_eq!(histogram.underflow(), 0);
+        assert_eq!(histogram.overflow(), 1);
+        // NaN is ignored
+        assert_eq!(histogram.count(), 6);
+        assert_eq!(histogram.total(), 18.5);
+    }
+
+    #[test]
+    fn test_empty() {
+        let histogram = Histogram::fixed_width(0.0, 1.0, 2).unwrap();
+        assert_eq!(histogram.bucket_counts(), [0, 0]);
+        assert_eq!(histogram.count(), 0);
+        assert_eq!(histogram.total(), 0.0);
+    }
+
+    #[test]
+    fn test_invalid_boundaries() {
+        assert_eq!(Histogram::with_boundaries(vec![1.0]), Err(HistogramError::TooFewBoundaries));
+        assert_eq!(Histogram::with_boundaries(vec![0.0, 0.0]), Err(HistogramError::InvalidBoundaries));
+        assert_eq!(Histogram::with_boundaries(vec![1.0, 0.0]), Err(HistogramError::InvalidBoundaries));
+        assert_eq!(
+            Histogram::with_boundaries(vec![0.0, f64::INFINITY]),
+            Err(HistogramError::InvalidBoundaries)
+        );
+        assert_eq!(Histogram::fixed_width(0.0, 1.0, 0), Err(HistogramError::TooFewBoundaries));
+        assert_eq!(Histogram::fixed_width(0.0, 0.0, 3), Err(HistogramError::InvalidBoundaries));
+        assert_eq!(Histogram::fixed_width(0.0, f64::NAN, 3), Err(HistogramError::InvalidBoundaries));
+    }
+}
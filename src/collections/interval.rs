@@ -0,0 +1,327 @@
+//! Intervals with inclusive or exclusive bounds.
+//!
+//! This module provides the `Interval` struct, which unifies inclusive ranges such as
+//! `[1, 3]`, half-open ranges such as `[1, 3)`, and open ranges such as `(1, 3)`.
+
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::ops::{Range, RangeInclusive};
+
+/// Whether a bound of an [`Interval`] belongs to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoundKind {
+    /// The bound is part of the interval, as in `[1, 3]`
+    Inclusive,
+    /// The bound isn't part of the interval, as in `(1, 3)`
+    Exclusive,
+}
+
+/// An interval between two values, each bound being either inclusive or exclusive.
+///
+/// Unlike `RangeInclusive` and `Range`, which each cover a single kind of bounds,
+/// `Interval` tracks the kind of each bound, so that operations mixing inclusive and
+/// half-open intervals give exact results. Both convert into an `Interval`.
+///
+/// An interval whose start is after its end, or with equal bounds that aren't both
+/// inclusive, is empty.
+///
+/// # Type Parameters
+///
+/// * `T` - The type of the bounds. Must implement `Copy` and `PartialOrd`.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::collections::interval::Interval;
+///
+/// let a = Interval::closed_open(1, 3); // [1, 3)
+/// let b = Interval::open_closed(2, 5); // (2, 5]
+///
+/// assert_eq!(a.intersection(&b), Some(Interval::open(2, 3)));
+/// assert_eq!(a.union(&b), Some(Interval::closed(1, 5)));
+/// assert!(!a.contains(&3));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    /// The lower bound
+    start: T,
+    /// Whether the lower bound is part of the interval
+    start_kind: BoundKind,
+    /// The upper bound
+    end: T,
+    /// Whether the upper bound is part of the interval
+    end_kind: BoundKind,
+}
+
+impl<T> Interval<T>
+where
+    T: Copy + PartialOrd,
+{
+    /// Creates an interval with the given bounds and bound kinds.
+    pub fn new(start: T, start_kind: BoundKind, end: T, end_kind: BoundKind) -> Self {
+        Self { start, start_kind, end, end_kind }
+    }
+
+    /// Creates the interval `[start, end]`, both bounds included.
+    pub fn closed(start: T, end: T) -> Self {
+        Self::new(start, BoundKind::Inclusive, end, BoundKind::Inclusive)
+    }
+
+    /// Creates the interval `(start, end)`, both bounds excluded.
+    pub fn open(start: T, end: T) -> Self {
+        Self::new(start, BoundKind::Exclusive, end, BoundKind::Exclusive)
+    }
+
+    /// Creates the interval `[start, end)`, like `start..end`.
+    pub fn closed_open(start: T, end: T) -> Self {
+        Self::new(start, BoundKind::Inclusive, end, BoundKind::Exclusive)
+    }
+
+    /// Creates the interval `(start, end]`.
+    pub fn open_closed(start: T, end: T) -> Self {
+        Self::new(start, BoundKind::Exclusive, end, BoundKind::Inclusive)
+    }
+
+    /// Returns the lower bound and its kind.
+    pub fn start(&self) -> (T, BoundKind) {
+        (self.start, self.start_kind)
+    }
+
+    /// Returns the upper bound and its kind.
+    pub fn end(&self) -> (T, BoundKind) {
+        (self.end, self.end_kind)
+    }
+
+    /// Checks whether the interval contains no value.
+    ///
+    /// Intervals with incomparable bounds, such as `NaN`, are empty.
+    pub fn is_empty(&self) -> bool {
+        match self.start.partial_cmp(&self.end) {
+            Some(Ordering::Less) => false,
+            Some(Ordering::Equal) => {
+                self.start_kind == BoundKind::Exclusive || self.end_kind == BoundKind::Exclusive
+            }
+            _ => true,
+        }
+    }
+
+    /// Checks whether `value` is in the interval, taking the kind of each bound into account.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::interval::Interval;
+    ///
+    /// let interval = Interval::open_closed(1, 3);
+    /// assert!(!interval.contains(&1));
+    /// assert!(interval.contains(&3));
+    /// ```
+    pub fn contains(&self, value: &T) -> bool {
+        let after_start = match self.start_kind {
+            BoundKind::Inclusive => self.start <= *value,
+            BoundKind::Exclusive => self.start < *value,
+        };
+        let before_end = match self.end_kind {
+            BoundKind::Inclusive => *value <= self.end,
+            BoundKind::Exclusive => *value < self.end,
+        };
+        after_start && before_end
+    }
+
+    /// Finds the intersection of two intervals.
+    ///
+    /// Where both intervals share a bound value, the result's bound is only inclusive if
+    /// it is in both.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The interval to intersect with.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Interval<T>)` - The values in both intervals.
+    /// * `None` - If no value is in both intervals.
+    pub fn intersection(&self, other: &Interval<T>) -> Option<Interval<T>> {
+        let (start, start_kind) = pick_bound(self.start(), other.start(), Ordering::Greater, BoundKind::Exclusive);
+        let (end, end_kind) = pick_bound(self.end(), other.end(), Ordering::Less, BoundKind::Exclusive);
+        let intersection = Self::new(start, start_kind, end, end_kind);
+        (!intersection.is_empty()).then_some(intersection)
+    }
+
+    /// Finds the union of two intervals, if it is itself an interval.
+    ///
+    /// Intervals that overlap, or touch at a bound included in at least one of them,
+    /// such as `[1, 3)` and `[3, 5]`, merge into a single interval. An empty interval
+    /// leaves the other one unchanged.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The interval to merge with.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Interval<T>)` - The values in either interval.
+    /// * `None` - If there is a gap between the intervals, or both are empty.
+    pub fn union(&self, other: &Interval<T>) -> Option<Interval<T>> {
+        match (self.is_empty(), other.is_empty()) {
+            (true, true) => return None,
+            (true, false) => return Some(*other),
+            (false, true) => return Some(*self),
+            (false, false) => {}
+        }
+
+        // Order the intervals by their start, to only check the first end against the second start
+        let (first, second) = match self.start.partial_cmp(&other.start) {
+            Some(Ordering::Greater) => (other, self),
+            _ => (self, other),
+        };
+        let connected = match first.end.partial_cmp(&second.start) {
+            Some(Ordering::Greater) => true,
+            Some(Ordering::Equal) => {
+                first.end_kind == BoundKind::Inclusive || second.start_kind == BoundKind::Inclusive
+            }
+            _ => false,
+        };
+        if !connected {
+            return None;
+        }
+
+        let (start, start_kind) = pick_bound(self.start(), other.start(), Ordering::Less, BoundKind::Inclusive);
+        let (end, end_kind) = pick_bound(self.end(), other.end(), Ordering::Greater, BoundKind::Inclusive);
+        Some(Self::new(start, start_kind, end, end_kind))
+    }
+}
+
+/// Picks the bound of `a` and `b` whose value compares to the other as `preferred`.
+/// When both values are equal, the bounds keep their kind if they agree on it, and
+/// take `tie_kind` otherwise.
+fn pick_bound<T>(a: (T, BoundKind), b: (T, BoundKind), preferred: Ordering, tie_kind: BoundKind) -> (T, BoundKind)
+where
+    T: Copy + PartialOrd,
+{
+    match a.0.partial_cmp(&b.0) {
+        Some(Ordering::Equal) => {
+            let kind = match (a.1, b.1) {
+                (BoundKind::Inclusive, BoundKind::Inclusive) => BoundKind::Inclusive,
+                (BoundKind::Exclusive, BoundKind::Exclusive) => BoundKind::Exclusive,
+                _ => tie_kind,
+            };
+            (a.0, kind)
+        }
+        Some(ordering) if ordering == preferred => a,
+        _ => b,
+    }
+}
+
+impl<T> From<RangeInclusive<T>> for Interval<T>
+where
+    T: Copy + PartialOrd,
+{
+    /// Converts `start..=end` into `[start, end]`.
+    fn from(range: RangeInclusive<T>) -> Self {
+        Self::closed(*range.start(), *range.end())
+    }
+}
+
+impl<T> From<Range<T>> for Interval<T>
+where
+    T: Copy + PartialOrd,
+{
+    /// Converts `start..end` into `[start, end)`.
+    fn from(range: Range<T>) -> Self {
+        Self::closed_open(range.start, range.end)
+    }
+}
+
+impl<T> Display for Interval<T>
+where
+    T: Display,
+{
+    /// Formats the interval in mathematical notation, e.g. `[1, 3)`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let open = if self.start_kind == BoundKind::Inclusive { '[' } else { '(' };
+        let close = if self.end_kind == BoundKind::Inclusive { ']' } else { ')' };
+        write!(f, "{open}{}, {}{close}", self.start, self.end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains() {
+        let closed = Interval::closed(1, 3);
+        assert!(closed.contains(&1) && closed.contains(&3));
+
+        let open = Interval::open(1, 3);
+        assert!(!open.contains(&1) && open.contains(&2) && !open.contains(&3));
+
+        let half_open = Interval::from(1.0..3.0);
+        assert!(half_open.contains(&1.0) && !half_open.contains(&3.0));
+    }
+
+    #[test]
+    fn test_is_empty() {
+        assert!(!Interval::closed(2, 2).is_empty());
+        assert!(Interval::closed_open(2, 2).is_empty());
+        assert!(Interval::open(2, 2).is_empty());
+        assert!(Interval::closed(3, 2).is_empty());
+        assert!(Interval::closed(f64::NAN, 1.0).is_empty());
+        // Empty over the integers, but not over the reals
+        assert!(!Interval::open(2, 3).is_empty());
+    }
+
+    #[test]
+    fn test_mixed_bound_intersection() {
+        let a = Interval::closed_open(1, 3);
+        let b = Interval::open_closed(2, 5);
+        assert_eq!(a.intersection(&b), Some(Interval::open(2, 3)));
+        assert_eq!(b.intersection(&a), Some(Interval::open(2, 3)));
+
+        // On a shared bound value, the bound is only inclusive if it is in both
+        let c = Interval::closed(1, 3);
+        assert_eq!(a.intersection(&c), Some(Interval::closed_open(1, 3)));
+        assert_eq!(c.intersection(&Interval::closed(1, 5)), Some(Interval::closed(1, 3)));
+    }
+
+    #[test]
+    fn test_empty_intersection() {
+        // Touching at a bound excluded from one side
+        let a = Interval::closed_open(1, 3);
+        let b = Interval::closed(3, 5);
+        assert_eq!(a.intersection(&b), None);
+
+        // Touching at a bound included in both
+        let c = Interval::closed(1, 3);
+        assert_eq!(c.intersection(&b), Some(Interval::closed(3, 3)));
+
+        assert_eq!(Interval::closed(1, 2).intersection(&Interval::closed(4, 5)), None);
+        assert_eq!(Interval::open(1, 2).intersection(&Interval::open(2, 3)), None);
+    }
+
+    #[test]
+    fn test_union() {
+        let a = Interval::closed_open(1, 3);
+        let b = Interval::open_closed(2, 5);
+        assert_eq!(a.union(&b), Some(Interval::closed(1, 5)));
+
+        // Touching intervals merge when the shared bound is in one of them
+        assert_eq!(a.union(&Interval::closed(3, 5)), Some(Interval::closed(1, 5)));
+        assert_eq!(Interval::open(3, 5).union(&a), None);
+
+        assert_eq!(Interval::closed(1, 2).union(&Interval::closed(4, 5)), None);
+        assert_eq!(Interval::open(1, 5).union(&Interval::closed(1, 2)), Some(Interval::closed_open(1, 5)));
+
+        // Empty intervals don't take part
+        assert_eq!(a.union(&Interval::open(9, 9)), Some(a));
+        assert_eq!(Interval::open(9, 9).union(&Interval::open(0, 0)), None);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Interval::closed_open(1, 3).to_string(), "[1, 3)");
+        assert_eq!(Interval::from(1..=3).to_string(), "[1, 3]");
+        assert_eq!(Interval::open_closed(1.5, 2.5).to_string(), "(1.5, 2.5]");
+    }
+}
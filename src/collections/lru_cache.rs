@@ -0,0 +1,221 @@
+//! A small least-recently-used cache.
+//!
+//! This module provides the `LruCache` struct, a bounded cache that, like `VecMap`,
+//! only requires its keys to implement `PartialEq`.
+
+/// A bounded cache evicting its least-recently-used entry when full.
+///
+/// Like [`VecMap`](super::vec_map::VecMap), the entries are stored in a vector and every
+/// lookup is a linear scan, so this is only meant for small caches, of a few dozen
+/// entries at most. In exchange, keys only need to implement `PartialEq`.
+///
+/// Both `insert` and `get` mark an entry as the most recently used; `peek` doesn't.
+///
+/// # Type Parameters
+///
+/// * `K` - The type of the keys.
+/// * `V` - The type of the values.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::collections::lru_cache::LruCache;
+///
+/// let mut cache = LruCache::new(2);
+/// cache.insert("a", 1);
+/// cache.insert("b", 2);
+///
+/// // Reading "a" makes "b" the least recently used
+/// assert_eq!(cache.get(&"a"), Some(&1));
+/// cache.insert("c", 3);
+///
+/// assert!(cache.contains_key(&"a"));
+/// assert!(!cache.contains_key(&"b"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct LruCache<K, V> {
+    /// The entries of the cache, from the least to the most recently used
+    entries: Vec<(K, V)>,
+    /// The maximum number of entries the cache can hold
+    capacity: usize,
+}
+
+impl<K, V> LruCache<K, V>
+where
+    K: PartialEq,
+{
+    /// Creates a new, empty `LruCache` holding at most `capacity` entries.
+    ///
+    /// # Parameters
+    ///
+    /// * `capacity` - The maximum number of entries the cache can hold.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero, since such a cache could never hold an entry.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "LruCache capacity must be greater than zero");
+        Self { entries: Vec::with_capacity(capacity), capacity }
+    }
+
+    /// Returns a reference to the value associated with `key`, marking it as the most
+    /// recently used.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - The key to look up.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(&V)` - The value associated with `key`.
+    /// * `None` - If the cache doesn't contain `key`.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let index = self.position(key)?;
+        let entry = self.entries.remove(index);
+        self.entries.push(entry);
+        self.entries.last().map(|(_, v)| v)
+    }
+
+    /// Returns a reference to the value associated with `key`, without changing its recency.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.position(key).map(|index| &self.entries[index].1)
+    }
+
+    /// Inserts a key-value pair into the cache, marking it as the most recently used.
+    ///
+    /// If the cache is full and doesn't contain `key`, the least recently used entry is
+    /// evicted to make room.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - The key to insert.
+    /// * `value` - The value to associate with `key`.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(V)` - The value previously associated with `key`.
+    /// * `None` - If the cache didn't contain `key`.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let previous = match self.position(&key) {
+            Some(index) => Some(self.entries.remove(index).1),
+            None => {
+                if self.entries.len() == self.capacity {
+                    self.entries.remove(0);
+                }
+                None
+            }
+        };
+        self.entries.push((key, value));
+        previous
+    }
+
+    /// Removes `key` from the cache.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(V)` - The value that was associated with `key`.
+    /// * `None` - If the cache didn't contain `key`.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.position(key).map(|index| self.entries.remove(index).1)
+    }
+
+    /// Checks whether the cache contains `key`, without changing its recency.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Returns the number of entries in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the maximum number of entries the cache can hold.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the index of the entry for `key`, if any.
+    fn position(&self, key: &K) -> Option<usize> {
+        self.entries.iter().position(|(k, _)| k == key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eviction_order() {
+        let mut cache = LruCache::new(3);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        cache.insert(3, "c");
+        assert_eq!(cache.len(), 3);
+
+        // Entries are evicted in insertion order when nothing is read
+        cache.insert(4, "d");
+        assert!(!cache.contains_key(&1));
+        cache.insert(5, "e");
+        assert!(!cache.contains_key(&2));
+
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.peek(&3), Some(&"c"));
+        assert_eq!(cache.peek(&4), Some(&"d"));
+        assert_eq!(cache.peek(&5), Some(&"e"));
+    }
+
+    #[test]
+    fn test_get_promotes() {
+        let mut cache = LruCache::new(3);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        cache.insert(3, "c");
+
+        assert_eq!(cache.get(&1), Some(&"a"));
+        cache.insert(4, "d");
+        // 2 was the least recently used once 1 was read
+        assert!(cache.contains_key(&1));
+        assert!(!cache.contains_key(&2));
+
+        // Peeking doesn't promote
+        assert_eq!(cache.peek(&3), Some(&"c"));
+        cache.insert(5, "e");
+        assert!(!cache.contains_key(&3));
+        assert_eq!(cache.get(&6), None);
+    }
+
+    #[test]
+    fn test_insert_existing_promotes() {
+        let mut cache = LruCache::new(2);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+
+        assert_eq!(cache.insert(1, "c"), Some("a"));
+        assert_eq!(cache.len(), 2);
+        cache.insert(3, "d");
+
+        assert_eq!(cache.peek(&1), Some(&"c"));
+        assert!(!cache.contains_key(&2));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut cache = LruCache::new(2);
+        cache.insert(1.5, "a");
+        assert_eq!(cache.remove(&1.5), Some("a"));
+        assert_eq!(cache.remove(&1.5), None);
+        assert!(cache.is_empty());
+        assert_eq!(cache.capacity(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "LruCache capacity must be greater than zero")]
+    fn test_zero_capacity() {
+        LruCache::<i32, i32>::new(0);
+    }
+}
@@ -5,9 +5,23 @@
 //! ## Submodules
 //!
 //! - `averaging_buffer`: A buffer that maintains a running average of its elements
+//! - `ewma_buffer`: An exponentially weighted moving average, reacting faster than a window
+//! - `histogram`: A histogram counting values into fixed-width or explicit buckets
+//! - `interval`: Intervals with inclusive or exclusive bounds, unifying the range types
+//! - `lru_cache`: A small least-recently-used cache, for keys that only implement `PartialEq`
 //! - `more_hashset`: Extensions for the standard library's `HashSet` type
 //! - `more_range`: Extensions for the standard library's `RangeInclusive` type
+//! - `multi_map`: A map associating each key with several values
+//! - `ordered_set`: A set iterating over its elements in insertion order
+//! - `vec_map`: A small map backed by a vector, for keys that only implement `PartialEq`
 
 pub mod averaging_buffer;
+pub mod ewma_buffer;
+pub mod histogram;
+pub mod interval;
+pub mod lru_cache;
 pub mod more_hashset;
 pub mod more_range;
+pub mod multi_map;
+pub mod ordered_set;
+pub mod vec_map;
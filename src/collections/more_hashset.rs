@@ -3,7 +3,7 @@
 //! This module provides additional functionality for `HashSet` through the
 //! `MoreHashSet` trait, including methods for comparing sets and filtering elements.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 use std::mem;
 
@@ -14,7 +14,9 @@ use std::mem;
 /// - Present only in the second set (`Added`)
 /// - Present only in the first set (`Removed`)
 ///
-/// This enum is used by the `diff` method to represent these states.
+/// This enum is used by the `diff` method to represent these states. The `diff_by_key`
+/// method, which compares elements by identity rather than full equality, can also
+/// report an element whose content changed (`Changed`).
 ///
 /// # Examples
 ///
@@ -41,6 +43,47 @@ pub enum DiffItem<T> {
     Added(T),
     /// The item is present only in the first set.
     Removed(T),
+    /// The item is present in both sets with the same identity but a different content.
+    Changed {
+        /// The item from the first set.
+        old: T,
+        /// The item from the second set.
+        new: T,
+    },
+}
+
+impl<T> DiffItem<T> {
+    /// Maps the item with a function, keeping its state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::DiffItem;
+    ///
+    /// assert_eq!(DiffItem::Added(2).map(|x| x * 10), DiffItem::Added(20));
+    /// ```
+    pub fn map<U, F>(self, mut f: F) -> DiffItem<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        match self {
+            DiffItem::Same(item) => DiffItem::Same(f(item)),
+            DiffItem::Added(item) => DiffItem::Added(f(item)),
+            DiffItem::Removed(item) => DiffItem::Removed(f(item)),
+            DiffItem::Changed { old, new } => DiffItem::Changed { old: f(old), new: f(new) },
+        }
+    }
+}
+
+impl<T> DiffItem<&T>
+where
+    T: Clone,
+{
+    /// Turns an item borrowing its element, like those of `diff_ref`, into an owned one
+    /// by cloning the element.
+    pub fn cloned(self) -> DiffItem<T> {
+        self.map(T::clone)
+    }
 }
 
 /// Extension trait for `HashSet` providing additional functionality.
@@ -50,7 +93,8 @@ pub enum DiffItem<T> {
 ///
 /// # Type Parameters
 ///
-/// * `T` - The type of elements in the set. Must implement `Eq`, `Hash`, and `Clone`.
+/// * `T` - The type of elements in the set. Must implement `Eq` and `Hash`, and `Clone` for
+///   the methods returning copies of the elements.
 ///
 /// # Examples
 ///
@@ -75,7 +119,7 @@ pub enum DiffItem<T> {
 /// ```
 pub trait MoreHashSet<T>
 where
-    T: Eq + Hash + Clone,
+    T: Eq + Hash,
 {
     /// Compares two sets and returns a vector of `DiffItem`s representing the differences.
     ///
@@ -117,7 +161,36 @@ where
     /// assert_eq!(added_count, 1);   // 3 is only in set2
     /// assert_eq!(removed_count, 1); // 1 is only in set1
     /// ```
-    fn diff(&self, other: &HashSet<T>) -> Vec<DiffItem<T>>;
+    fn diff(&self, other: &HashSet<T>) -> Vec<DiffItem<T>>
+    where
+        T: Clone;
+
+    /// Compares two sets like [`MoreHashSet::diff`], but borrows the elements instead of
+    /// cloning them, so the elements don't need to implement `Clone`.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The set to compare with.
+    ///
+    /// # Returns
+    ///
+    /// A vector of `DiffItem`s borrowing the elements from both sets. Use
+    /// [`DiffItem::cloned`] to turn one into an owned item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::{MoreHashSet, DiffItem};
+    /// use std::collections::HashSet;
+    ///
+    /// let set1 = HashSet::from([String::from("a"), String::from("b")]);
+    /// let set2 = HashSet::from([String::from("b")]);
+    ///
+    /// let diff = set1.diff_ref(&set2);
+    /// assert_eq!(diff.len(), 2);
+    /// assert!(diff.contains(&DiffItem::Removed(&String::from("a"))));
+    /// ```
+    fn diff_ref<'a>(&'a self, other: &'a HashSet<T>) -> Vec<DiffItem<&'a T>>;
 
     /// Removes elements from the set that match a predicate and returns them as a new set.
     ///
@@ -160,23 +233,398 @@ where
     fn drain_filter<F>(&mut self, predicate: F) -> HashSet<T>
     where
         F: FnMut(&T) -> bool;
+
+    /// Groups the elements of the set by a computed key.
+    ///
+    /// Each element is cloned into the set associated with the key returned by `key_fn`.
+    ///
+    /// # Parameters
+    ///
+    /// * `key_fn` - A function that computes the grouping key of an element.
+    ///
+    /// # Returns
+    ///
+    /// A `HashMap` from each key to the set of elements sharing that key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::MoreHashSet;
+    /// use std::collections::HashSet;
+    ///
+    /// let set: HashSet<i32> = [1, 2, 3, 4, 5].into_iter().collect();
+    /// let groups = set.group_into(|&x| x % 2 == 0);
+    ///
+    /// assert_eq!(groups[&true], [2, 4].into_iter().collect());
+    /// assert_eq!(groups[&false], [1, 3, 5].into_iter().collect());
+    /// ```
+    fn group_into<K, F>(&self, key_fn: F) -> HashMap<K, HashSet<T>>
+    where
+        T: Clone,
+        K: Eq + Hash,
+        F: Fn(&T) -> K;
+
+    /// Flips the membership of a value in the set.
+    ///
+    /// The value is removed if it is present, and inserted otherwise.
+    ///
+    /// # Parameters
+    ///
+    /// * `value` - The value to toggle.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the value is now in the set, `false` if it was removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::MoreHashSet;
+    /// use std::collections::HashSet;
+    ///
+    /// let mut selection = HashSet::new();
+    /// assert!(selection.toggle(1));  // 1 is now selected
+    /// assert!(!selection.toggle(1)); // 1 is no longer selected
+    /// assert!(selection.is_empty());
+    /// ```
+    fn toggle(&mut self, value: T) -> bool;
+
+    /// Computes the Cartesian product of this set with another set.
+    ///
+    /// The result contains every `(t, u)` pair where `t` is in this set and `u` is in
+    /// `other`, so its size is `|self| * |other|`.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The set to combine with.
+    ///
+    /// # Returns
+    ///
+    /// A `HashSet` of all the pairs, empty if either set is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::MoreHashSet;
+    /// use std::collections::HashSet;
+    ///
+    /// let sizes: HashSet<_> = ["S", "L"].into_iter().collect();
+    /// let colors: HashSet<_> = ["red", "blue"].into_iter().collect();
+    ///
+    /// let product = sizes.cartesian_product(&colors);
+    /// assert_eq!(product.len(), 4);
+    /// assert!(product.contains(&("L", "red")));
+    /// ```
+    fn cartesian_product<U>(&self, other: &HashSet<U>) -> HashSet<(T, U)>
+    where
+        T: Clone,
+        U: Eq + Hash + Clone;
+
+    /// Finds the most common key among the elements of the set.
+    ///
+    /// Each element is mapped to a key with `key_fn`, and the key shared by the most
+    /// elements is returned along with its count. When several keys are tied, the
+    /// smallest one is returned, so the result doesn't depend on the iteration order
+    /// of the set.
+    ///
+    /// # Parameters
+    ///
+    /// * `key_fn` - A function that computes the key of an element.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((K, usize))` - The most common key and the number of elements mapped to it.
+    /// * `None` - If the set is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::MoreHashSet;
+    /// use std::collections::HashSet;
+    ///
+    /// let set: HashSet<i32> = [1, 2, 3, 4, 5].into_iter().collect();
+    /// assert_eq!(set.most_common_by(|&x| x % 2), Some((1, 3)));
+    /// ```
+    fn most_common_by<K, F>(&self, key_fn: F) -> Option<(K, usize)>
+    where
+        K: Eq + Hash + Ord,
+        F: Fn(&T) -> K;
+
+    /// Transforms and filters the set in a single pass, collecting the results into a new set.
+    ///
+    /// # Parameters
+    ///
+    /// * `f` - A function mapping each element to `Some(value)` to keep, or `None` to drop.
+    ///
+    /// # Returns
+    ///
+    /// A new `HashSet` containing the `Some` results of `f`. Elements mapping to the same
+    /// value are merged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::MoreHashSet;
+    /// use std::collections::HashSet;
+    ///
+    /// let words: HashSet<&str> = ["1", "two", "3"].into_iter().collect();
+    /// let numbers = words.filter_map_into(|w| w.parse::<i32>().ok());
+    /// assert_eq!(numbers, [1, 3].into_iter().collect());
+    /// ```
+    fn filter_map_into<U, F>(&self, f: F) -> HashSet<U>
+    where
+        U: Eq + Hash,
+        F: Fn(&T) -> Option<U>;
+
+    /// Splits the elements of the set into chunks of at most `size` elements, for batch
+    /// processing.
+    ///
+    /// Elements are cloned, in the set's iteration order: arbitrary, but the same for
+    /// every call on an unmodified set. Every chunk but the last holds exactly `size`
+    /// elements.
+    ///
+    /// # Parameters
+    ///
+    /// * `size` - The maximum number of elements in a chunk.
+    ///
+    /// # Returns
+    ///
+    /// The chunks, holding every element of the set exactly once. An empty set gives no chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero, like `slice::chunks`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::MoreHashSet;
+    /// use std::collections::HashSet;
+    ///
+    /// let set: HashSet<i32> = (1..=5).collect();
+    /// let chunks = set.chunks(2);
+    /// assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), [2, 2, 1]);
+    /// ```
+    fn chunks(&self, size: usize) -> Vec<Vec<T>>
+    where
+        T: Clone;
+
+    /// Computes the Jaccard similarity of two sets: the size of their intersection over
+    /// the size of their union, `|A ∩ B| / |A ∪ B|`.
+    ///
+    /// The result ranges from `0.0` for disjoint sets to `1.0` for equal sets. Two empty
+    /// sets have no union to compare, and give `0.0`. The intersection is counted without
+    /// building a new set.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The set to compare with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::MoreHashSet;
+    /// use std::collections::HashSet;
+    ///
+    /// let set1 = HashSet::from([1, 2, 3]);
+    /// let set2 = HashSet::from([2, 3, 4]);
+    /// assert_eq!(set1.jaccard_similarity(&set2), 0.5); // 2 common elements out of 4
+    /// ```
+    fn jaccard_similarity(&self, other: &HashSet<T>) -> f64;
+
+    /// Computes the overlap coefficient of two sets: the size of their intersection over
+    /// the size of the smaller set, `|A ∩ B| / min(|A|, |B|)`.
+    ///
+    /// Unlike the Jaccard similarity, a set has an overlap coefficient of `1.0` with any
+    /// of its supersets. When either set is empty, the result is `0.0`. The intersection
+    /// is counted without building a new set.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The set to compare with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::MoreHashSet;
+    /// use std::collections::HashSet;
+    ///
+    /// let set1 = HashSet::from([1, 2]);
+    /// let set2 = HashSet::from([1, 2, 3, 4]);
+    /// assert_eq!(set1.overlap_coefficient(&set2), 1.0);
+    /// ```
+    fn overlap_coefficient(&self, other: &HashSet<T>) -> f64;
+
+    /// Compares two sets by the key of their elements, rather than by full equality.
+    ///
+    /// This is meant for elements whose identity is only a subset of their fields. Elements
+    /// are matched by their key, then each matched pair is either:
+    /// - `Same`, when both elements are equal (the element from this set is kept)
+    /// - `Changed`, when the elements share a key but differ otherwise
+    ///
+    /// Unmatched elements are `Removed` from this set or `Added` in the other one.
+    ///
+    /// Keys are expected to be unique within each set. When they aren't, equal elements
+    /// are matched first, then the rest of the elements sharing a key are paired up
+    /// arbitrarily, the leftovers being `Removed` or `Added`.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The set to compare with.
+    /// * `key_fn` - A function that extracts the identity of an element.
+    ///
+    /// # Returns
+    ///
+    /// A vector of `DiffItem`s representing the differences between the sets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::{MoreHashSet, DiffItem};
+    /// use std::collections::HashSet;
+    ///
+    /// // (id, version) pairs, identified by their id
+    /// let before = HashSet::from([(1, "v1"), (2, "v1")]);
+    /// let after = HashSet::from([(1, "v1"), (2, "v2")]);
+    ///
+    /// let diff = before.diff_by_key(&after, |&(id, _)| id);
+    /// assert_eq!(diff.len(), 2);
+    /// assert!(diff.contains(&DiffItem::Same((1, "v1"))));
+    /// assert!(diff.contains(&DiffItem::Changed { old: (2, "v1"), new: (2, "v2") }));
+    /// ```
+    fn diff_by_key<K, F>(&self, other: &HashSet<T>, key_fn: F) -> Vec<DiffItem<T>>
+    where
+        T: Clone,
+        K: Eq + Hash,
+        F: Fn(&T) -> K;
+
+    /// Removes elements from the set that match a predicate and returns them in a vector.
+    ///
+    /// This is [`MoreHashSet::drain_filter`] without the cost of building a second set,
+    /// for when the removed elements are only iterated over. The retained elements are
+    /// the same, and the removed ones come in an unspecified order.
+    ///
+    /// # Parameters
+    ///
+    /// * `predicate` - A function that returns `true` for elements that should be removed.
+    ///
+    /// # Returns
+    ///
+    /// A vector containing the removed elements, in no particular order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::MoreHashSet;
+    /// use std::collections::HashSet;
+    ///
+    /// let mut set: HashSet<i32> = (1..=6).collect();
+    /// let mut removed = set.drain_filter_vec(|&x| x % 3 == 0);
+    ///
+    /// removed.sort();
+    /// assert_eq!(removed, [3, 6]);
+    /// assert_eq!(set.len(), 4);
+    /// ```
+    fn drain_filter_vec<F>(&mut self, predicate: F) -> Vec<T>
+    where
+        F: FnMut(&T) -> bool;
+
+    /// Replays a diff onto the set: `Added` items are inserted, `Removed` items removed,
+    /// and `Same` items ignored. A `Changed` item has its old element replaced by its new one.
+    ///
+    /// Applying `a.diff(&b)` onto a copy of `a` gives a set equal to `b`, which allows
+    /// shipping incremental updates of a set instead of the whole set.
+    ///
+    /// # Parameters
+    ///
+    /// * `diff` - The items to apply, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::MoreHashSet;
+    /// use std::collections::HashSet;
+    ///
+    /// let before = HashSet::from([1, 2, 3]);
+    /// let after = HashSet::from([2, 3, 4]);
+    ///
+    /// let mut replica = before.clone();
+    /// replica.apply_diff(&before.diff(&after));
+    /// assert_eq!(replica, after);
+    /// ```
+    fn apply_diff(&mut self, diff: &[DiffItem<T>])
+    where
+        T: Clone;
+
+    /// Merges another set into this one, combining the elements present in both.
+    ///
+    /// This is a union for elements whose equality is an identity, while the rest of their
+    /// content may differ: instead of keeping one of two equal elements arbitrarily,
+    /// `combine` builds the element to keep from both.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The set to merge into this one.
+    /// * `combine` - A function called with the element of this set and the equal element
+    ///   of `other`, returning the element to keep. It should return an element equal to
+    ///   them, or the result may collide with another element and be dropped.
+    ///
+    /// # Returns
+    ///
+    /// The union of both sets, with the colliding elements combined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::MoreHashSet;
+    /// use std::collections::HashSet;
+    /// use std::hash::{Hash, Hasher};
+    ///
+    /// // A counter identified by its name only
+    /// #[derive(Debug)]
+    /// struct Counter(&'static str, u32);
+    /// impl PartialEq for Counter {
+    ///     fn eq(&self, other: &Self) -> bool { self.0 == other.0 }
+    /// }
+    /// impl Eq for Counter {}
+    /// impl Hash for Counter {
+    ///     fn hash<H: Hasher>(&self, state: &mut H) { self.0.hash(state) }
+    /// }
+    ///
+    /// let set1 = HashSet::from([Counter("a", 1), Counter("b", 2)]);
+    /// let set2 = HashSet::from([Counter("a", 10)]);
+    ///
+    /// let merged = set1.merge_with(set2, |x, y| Counter(x.0, x.1 + y.1));
+    /// assert_eq!(merged.get(&Counter("a", 0)).unwrap().1, 11);
+    /// assert_eq!(merged.len(), 2);
+    /// ```
+    fn merge_with<F>(self, other: HashSet<T>, combine: F) -> HashSet<T>
+    where
+        F: Fn(T, T) -> T;
 }
 
 impl<T> MoreHashSet<T> for HashSet<T>
 where
-    T: Eq + Hash + Clone,
+    T: Eq + Hash,
 {
-    fn diff(&self, other: &HashSet<T>) -> Vec<DiffItem<T>> {
+    fn diff(&self, other: &HashSet<T>) -> Vec<DiffItem<T>>
+    where
+        T: Clone,
+    {
+        self.diff_ref(other).into_iter().map(DiffItem::cloned).collect()
+    }
+
+    fn diff_ref<'a>(&'a self, other: &'a HashSet<T>) -> Vec<DiffItem<&'a T>> {
         // Find elements that are in both sets
-        self.intersection(other).cloned()
-            .map(|item| DiffItem::Same(item))
+        self.intersection(other)
+            .map(DiffItem::Same)
             // Find elements that are only in self
-            .chain(self.difference(other).cloned()
-                .map(|item| DiffItem::Removed(item))
+            .chain(self.difference(other)
+                .map(DiffItem::Removed)
             )
             // Find elements that are only in other
-            .chain(other.difference(self).cloned()
-                .map(|item| DiffItem::Added(item))
+            .chain(other.difference(self)
+                .map(DiffItem::Added)
             )
             .collect()
     }
@@ -200,6 +648,251 @@ where
 
         removed
     }
+
+    fn group_into<K, F>(&self, key_fn: F) -> HashMap<K, HashSet<T>>
+    where
+        T: Clone,
+        K: Eq + Hash,
+        F: Fn(&T) -> K,
+    {
+        let mut groups: HashMap<K, HashSet<T>> = HashMap::new();
+        for item in self {
+            groups.entry(key_fn(item)).or_default().insert(item.clone());
+        }
+        groups
+    }
+
+    fn toggle(&mut self, value: T) -> bool {
+        // `remove` returns whether the value was present, in which case we are done
+        !self.remove(&value) && self.insert(value)
+    }
+
+    fn cartesian_product<U>(&self, other: &HashSet<U>) -> HashSet<(T, U)>
+    where
+        T: Clone,
+        U: Eq + Hash + Clone,
+    {
+        self.iter()
+            .flat_map(|t| other.iter().map(move |u| (t.clone(), u.clone())))
+            .collect()
+    }
+
+    fn most_common_by<K, F>(&self, key_fn: F) -> Option<(K, usize)>
+    where
+        K: Eq + Hash + Ord,
+        F: Fn(&T) -> K,
+    {
+        let mut counts: HashMap<K, usize> = HashMap::new();
+        for item in self {
+            *counts.entry(key_fn(item)).or_default() += 1;
+        }
+
+        // Highest count first, then smallest key
+        counts.into_iter()
+            .max_by(|(key1, count1), (key2, count2)| count1.cmp(count2).then_with(|| key2.cmp(key1)))
+    }
+
+    fn filter_map_into<U, F>(&self, f: F) -> HashSet<U>
+    where
+        U: Eq + Hash,
+        F: Fn(&T) -> Option<U>,
+    {
+        self.iter().filter_map(f).collect()
+    }
+
+    fn chunks(&self, size: usize) -> Vec<Vec<T>>
+    where
+        T: Clone,
+    {
+        assert!(size > 0, "chunk size must be greater than zero");
+        let elements: Vec<T> = self.iter().cloned().collect();
+        elements.chunks(size).map(<[T]>::to_vec).collect()
+    }
+
+    fn jaccard_similarity(&self, other: &HashSet<T>) -> f64 {
+        let common = self.intersection(other).count();
+        let union = self.len() + other.len() - common;
+        if union == 0 {
+            return 0.0;
+        }
+        common as f64 / union as f64
+    }
+
+    fn overlap_coefficient(&self, other: &HashSet<T>) -> f64 {
+        let smallest = self.len().min(other.len());
+        if smallest == 0 {
+            return 0.0;
+        }
+        self.intersection(other).count() as f64 / smallest as f64
+    }
+
+    fn diff_by_key<K, F>(&self, other: &HashSet<T>, key_fn: F) -> Vec<DiffItem<T>>
+    where
+        T: Clone,
+        K: Eq + Hash,
+        F: Fn(&T) -> K,
+    {
+        let mut others: HashMap<K, Vec<&T>> = HashMap::new();
+        for item in other {
+            others.entry(key_fn(item)).or_default().push(item);
+        }
+
+        // Equal elements are matched first, so they aren't paired up with another element
+        let mut unmatched = Vec::new();
+        let mut diff = Vec::with_capacity(self.len().max(other.len()));
+        for item in self {
+            let key = key_fn(item);
+            let candidates = others.get_mut(&key);
+            match candidates.and_then(|c| c.iter().position(|&o| o == item).map(|i| (c, i))) {
+                Some((candidates, i)) => {
+                    candidates.swap_remove(i);
+                    diff.push(DiffItem::Same(item.clone()));
+                }
+                None => unmatched.push((key, item)),
+            }
+        }
+
+        for (key, item) in unmatched {
+            match others.get_mut(&key).and_then(|candidates| candidates.pop()) {
+                Some(new) => diff.push(DiffItem::Changed { old: item.clone(), new: new.clone() }),
+                None => diff.push(DiffItem::Removed(item.clone())),
+            }
+        }
+        diff.extend(others.into_values().flatten().map(|item| DiffItem::Added(item.clone())));
+        diff
+    }
+
+    fn drain_filter_vec<F>(&mut self, mut predicate: F) -> Vec<T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        // Same strategy as `drain_filter`, collecting into a vector instead
+        let original = mem::take(self);
+        let mut removed = Vec::new();
+
+        for item in original {
+            if predicate(&item) {
+                removed.push(item);
+            } else {
+                self.insert(item);
+            }
+        }
+
+        removed
+    }
+
+    fn apply_diff(&mut self, diff: &[DiffItem<T>])
+    where
+        T: Clone,
+    {
+        for item in diff {
+            match item {
+                DiffItem::Same(_) => {}
+                DiffItem::Added(item) => {
+                    self.insert(item.clone());
+                }
+                DiffItem::Removed(item) => {
+                    self.remove(item);
+                }
+                DiffItem::Changed { old, new } => {
+                    self.remove(old);
+                    self.insert(new.clone());
+                }
+            }
+        }
+    }
+
+    fn merge_with<F>(mut self, other: HashSet<T>, combine: F) -> HashSet<T>
+    where
+        F: Fn(T, T) -> T,
+    {
+        for item in other {
+            let merged = match self.take(&item) {
+                Some(existing) => combine(existing, item),
+                None => item,
+            };
+            self.insert(merged);
+        }
+        self
+    }
+}
+
+/// Tracks the changes of a set between successive updates.
+///
+/// `DiffTracker` keeps the last version of a set it was given, and each call to
+/// `update` reports what was added and removed since then. This is handy for reactive
+/// code that diffs the same set every tick and only cares about the delta.
+///
+/// # Type Parameters
+///
+/// * `T` - The type of elements in the set. Must implement `Eq`, `Hash`, and `Clone`.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::collections::more_hashset::DiffTracker;
+/// use std::collections::HashSet;
+///
+/// let mut tracker = DiffTracker::new();
+///
+/// let (added, removed) = tracker.update(&HashSet::from([1, 2]));
+/// assert_eq!(added.len(), 2);
+/// assert!(removed.is_empty());
+///
+/// let (added, removed) = tracker.update(&HashSet::from([2, 3]));
+/// assert_eq!(added, [3]);
+/// assert_eq!(removed, [1]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiffTracker<T> {
+    /// The set given to the last update
+    previous: HashSet<T>,
+}
+
+impl<T> DiffTracker<T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Creates a new tracker, starting from an empty set.
+    pub fn new() -> Self {
+        Self { previous: HashSet::new() }
+    }
+
+    /// Creates a new tracker, starting from the given set.
+    pub fn with_baseline(baseline: HashSet<T>) -> Self {
+        Self { previous: baseline }
+    }
+
+    /// Returns the set given to the last update.
+    pub fn baseline(&self) -> &HashSet<T> {
+        &self.previous
+    }
+
+    /// Compares `current` with the previous set, and makes it the new baseline.
+    ///
+    /// # Parameters
+    ///
+    /// * `current` - The current version of the set.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the elements added and the elements removed since the last update,
+    /// in no particular order.
+    pub fn update(&mut self, current: &HashSet<T>) -> (Vec<T>, Vec<T>) {
+        let added = current.difference(&self.previous).cloned().collect();
+        let removed = self.previous.difference(current).cloned().collect();
+        self.previous.clone_from(current);
+        (added, removed)
+    }
+}
+
+impl<T> Default for DiffTracker<T>
+where
+    T: Eq + Hash + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
@@ -365,4 +1058,366 @@ mod tests {
         assert!(removed.contains("cherry"));
         assert!(removed.contains("elderberry"));
     }
+
+    #[test]
+    fn test_group_into() {
+        let set = set_from_slice(&["apple", "avocado", "banana", "blueberry", "cherry"]);
+        let groups = set.group_into(|s| s.chars().next().unwrap());
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[&'a'], set_from_slice(&["apple", "avocado"]));
+        assert_eq!(groups[&'b'], set_from_slice(&["banana", "blueberry"]));
+        assert_eq!(groups[&'c'], set_from_slice(&["cherry"]));
+    }
+
+    #[test]
+    fn test_toggle() {
+        let mut set = set_from_slice(&[1, 2]);
+
+        assert!(set.toggle(3));
+        assert!(set.contains(&3));
+
+        assert!(!set.toggle(3));
+        assert!(!set.contains(&3));
+
+        assert_eq!(set, set_from_slice(&[1, 2]));
+    }
+
+    #[test]
+    fn test_cartesian_product() {
+        let set1 = set_from_slice(&[1, 2]);
+        let set2 = set_from_slice(&["a", "b", "c"]);
+        let product = set1.cartesian_product(&set2);
+
+        assert_eq!(product.len(), 6);
+        for t in &set1 {
+            for u in &set2 {
+                assert!(product.contains(&(*t, *u)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_cartesian_product_empty() {
+        let set1 = set_from_slice(&[1, 2]);
+        let set2: HashSet<&str> = HashSet::new();
+
+        assert!(set1.cartesian_product(&set2).is_empty());
+        assert!(set2.cartesian_product(&set1).is_empty());
+    }
+
+    #[test]
+    fn test_most_common_by() {
+        let set = set_from_slice(&["apple", "avocado", "apricot", "banana", "cherry"]);
+        assert_eq!(set.most_common_by(|s| s.chars().next().unwrap()), Some(('a', 3)));
+
+        let empty: HashSet<&str> = HashSet::new();
+        assert_eq!(empty.most_common_by(|s| s.len()), None);
+    }
+
+    #[test]
+    fn test_most_common_by_tie() {
+        let set = set_from_slice(&["bear", "bee", "ant", "ape", "cat"]);
+        // 'a' and 'b' both have two members, the smallest key wins
+        for _ in 0..10 {
+            assert_eq!(set.most_common_by(|s| s.chars().next().unwrap()), Some(('a', 2)));
+        }
+    }
+
+    #[test]
+    fn test_diff_tracker() {
+        let mut tracker = DiffTracker::new();
+
+        let (mut added, removed) = tracker.update(&set_from_slice(&[1, 2, 3]));
+        added.sort();
+        assert_eq!(added, [1, 2, 3]);
+        assert!(removed.is_empty());
+
+        // Nothing changed since the last tick
+        let (added, removed) = tracker.update(&set_from_slice(&[1, 2, 3]));
+        assert!(added.is_empty());
+        assert!(removed.is_empty());
+
+        let (added, mut removed) = tracker.update(&set_from_slice(&[3, 4]));
+        removed.sort();
+        assert_eq!(added, [4]);
+        assert_eq!(removed, [1, 2]);
+
+        let (added, mut removed) = tracker.update(&HashSet::new());
+        removed.sort();
+        assert!(added.is_empty());
+        assert_eq!(removed, [3, 4]);
+        assert!(tracker.baseline().is_empty());
+    }
+
+    #[test]
+    fn test_diff_tracker_with_baseline() {
+        let mut tracker = DiffTracker::with_baseline(set_from_slice(&["a", "b"]));
+        let (added, removed) = tracker.update(&set_from_slice(&["b", "c"]));
+        assert_eq!(added, ["c"]);
+        assert_eq!(removed, ["a"]);
+    }
+
+    #[test]
+    fn test_filter_map_into() {
+        let set = set_from_slice(&["1", "2", "two", "02", "-7", ""]);
+        let numbers = set.filter_map_into(|s| s.parse::<i32>().ok());
+
+        // "2" and "02" parse to the same value, unparseable strings are dropped
+        assert_eq!(numbers, set_from_slice(&[1, 2, -7]));
+
+        let empty: HashSet<&str> = HashSet::new();
+        assert!(empty.filter_map_into(|s| s.parse::<i32>().ok()).is_empty());
+    }
+
+    #[test]
+    fn test_chunks() {
+        let set: HashSet<i32> = (0..10).collect();
+        let chunks = set.chunks(3);
+
+        let sizes: Vec<usize> = chunks.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [3, 3, 3, 1]);
+
+        // Every element appears exactly once
+        let flattened: Vec<i32> = chunks.iter().flatten().copied().collect();
+        assert_eq!(flattened.len(), set.len());
+        assert_eq!(flattened.into_iter().collect::<HashSet<_>>(), set);
+
+        // The order is stable across calls
+        assert_eq!(set.chunks(3), chunks);
+
+        assert_eq!(set.chunks(20).len(), 1);
+        assert!(HashSet::<i32>::new().chunks(3).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk size must be greater than zero")]
+    fn test_chunks_zero_size() {
+        set_from_slice(&[1]).chunks(0);
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct Entry {
+        id: u32,
+        content: &'static str,
+    }
+
+    #[test]
+    fn test_jaccard_similarity() {
+        let set = set_from_slice(&[1, 2, 3, 4]);
+        assert_eq!(set.jaccard_similarity(&set.clone()), 1.0);
+        assert_eq!(set.jaccard_similarity(&set_from_slice(&[5, 6])), 0.0);
+        assert_eq!(set.jaccard_similarity(&set_from_slice(&[3, 4, 5, 6])), 2.0 / 6.0);
+        assert_eq!(set_from_slice(&[3, 4, 5, 6]).jaccard_similarity(&set), 2.0 / 6.0);
+
+        let empty: HashSet<i32> = HashSet::new();
+        assert_eq!(empty.jaccard_similarity(&HashSet::new()), 0.0);
+        assert_eq!(empty.jaccard_similarity(&set), 0.0);
+    }
+
+    #[test]
+    fn test_overlap_coefficient() {
+        let set = set_from_slice(&[1, 2, 3, 4]);
+        assert_eq!(set.overlap_coefficient(&set.clone()), 1.0);
+        assert_eq!(set.overlap_coefficient(&set_from_slice(&[5, 6])), 0.0);
+        assert_eq!(set.overlap_coefficient(&set_from_slice(&[4, 5])), 0.5);
+        // A subset fully overlaps its superset
+        assert_eq!(set.overlap_coefficient(&set_from_slice(&[2, 3])), 1.0);
+
+        let empty: HashSet<i32> = HashSet::new();
+        assert_eq!(empty.overlap_coefficient(&HashSet::new()), 0.0);
+        assert_eq!(set.overlap_coefficient(&empty), 0.0);
+    }
+
+    #[test]
+    fn test_diff_by_key() {
+        let before: HashSet<Entry> = [(1, "a"), (2, "b"), (3, "c")].iter()
+            .map(|&(id, content)| Entry { id, content })
+            .collect();
+        let after: HashSet<Entry> = [(1, "a"), (2, "B"), (4, "d")].iter()
+            .map(|&(id, content)| Entry { id, content })
+            .collect();
+
+        let diff = before.diff_by_key(&after, |entry| entry.id);
+        assert_eq!(diff.len(), 4);
+        assert!(diff.contains(&DiffItem::Same(Entry { id: 1, content: "a" })));
+        assert!(diff.contains(&DiffItem::Changed {
+            old: Entry { id: 2, content: "b" },
+            new: Entry { id: 2, content: "B" },
+        }));
+        assert!(diff.contains(&DiffItem::Removed(Entry { id: 3, content: "c" })));
+        assert!(diff.contains(&DiffItem::Added(Entry { id: 4, content: "d" })));
+
+        // Full equality sees the changed entry as removed and added
+        assert_eq!(before.diff(&after).len(), 5);
+    }
+
+    #[test]
+    fn test_diff_by_key_duplicate_keys() {
+        let before = set_from_slice(&[(1, 'a'), (1, 'b'), (1, 'c')]);
+        let after = set_from_slice(&[(1, 'b'), (1, 'x')]);
+
+        let diff = before.diff_by_key(&after, |&(id, _)| id);
+        assert_eq!(diff.len(), 3);
+        assert!(diff.contains(&DiffItem::Same((1, 'b'))));
+        assert_eq!(diff.iter().filter(|item| matches!(item, DiffItem::Changed { new: (1, 'x'), .. })).count(), 1);
+        assert_eq!(diff.iter().filter(|item| matches!(item, DiffItem::Removed(_))).count(), 1);
+    }
+
+    /// An element that can't be cloned
+    #[derive(Debug, PartialEq, Eq, Hash)]
+    struct Handle(u32);
+
+    #[test]
+    fn test_diff_ref() {
+        let set1: HashSet<Handle> = [1, 2, 3].into_iter().map(Handle).collect();
+        let set2: HashSet<Handle> = [2, 3, 4].into_iter().map(Handle).collect();
+
+        let diff = set1.diff_ref(&set2);
+        assert_eq!(diff.len(), 4);
+        assert!(diff.contains(&DiffItem::Removed(&Handle(1))));
+        assert!(diff.contains(&DiffItem::Same(&Handle(2))));
+        assert!(diff.contains(&DiffItem::Same(&Handle(3))));
+        assert!(diff.contains(&DiffItem::Added(&Handle(4))));
+
+        // The borrowed items point into the sets
+        let added = diff.iter().find_map(|item| match item {
+            DiffItem::Added(handle) => Some(*handle),
+            _ => None,
+        });
+        assert!(std::ptr::eq(added.unwrap(), set2.get(&Handle(4)).unwrap()));
+
+        // Other methods that don't clone are available too
+        let mut set1 = set1;
+        assert!(set1.toggle(Handle(5)));
+        assert_eq!(set1.drain_filter(|handle| handle.0 > 2).len(), 2);
+    }
+
+    #[test]
+    fn test_diff_matches_diff_ref() {
+        let set1 = set_from_slice(&["a", "b", "c"]);
+        let set2 = set_from_slice(&["b", "d"]);
+
+        let mut owned = set1.diff(&set2).into_iter().map(|item| format!("{:?}", item)).collect::<Vec<_>>();
+        let mut borrowed = set1.diff_ref(&set2).into_iter()
+            .map(|item| format!("{:?}", item.cloned()))
+            .collect::<Vec<_>>();
+        owned.sort();
+        borrowed.sort();
+        assert_eq!(owned, borrowed);
+
+        assert_eq!(DiffItem::Changed { old: &1, new: &2 }.cloned(), DiffItem::Changed { old: 1, new: 2 });
+    }
+
+    #[test]
+    fn test_drain_filter_vec_partitions() {
+        let original = set_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        let mut set = original.clone();
+
+        let removed = set.drain_filter_vec(|&x| x % 4 == 0 || x == 1);
+        assert_eq!(removed.len(), 3);
+
+        // The retained set and the removed elements partition the original set
+        assert!(removed.iter().all(|x| !set.contains(x)));
+        let mut union: HashSet<i32> = set.clone();
+        union.extend(removed.iter().copied());
+        assert_eq!(union, original);
+        assert_eq!(set.len() + removed.len(), original.len());
+
+        // Same retained set as drain_filter
+        let mut other = original.clone();
+        let removed_set = other.drain_filter(|&x| x % 4 == 0 || x == 1);
+        assert_eq!(other, set);
+        assert_eq!(removed_set, removed.into_iter().collect());
+    }
+
+    #[test]
+    fn test_drain_filter_vec_none_and_all() {
+        let mut set = set_from_slice(&["a", "b"]);
+        assert!(set.drain_filter_vec(|_| false).is_empty());
+        assert_eq!(set.len(), 2);
+
+        assert_eq!(set.drain_filter_vec(|_| true).len(), 2);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_apply_diff_round_trip() {
+        let pairs: [(&[i32], &[i32]); 6] = [
+            (&[1, 2, 3], &[2, 3, 4]),
+            (&[1, 2, 3], &[1, 2, 3]),
+            (&[], &[1, 2]),
+            (&[1, 2], &[]),
+            (&[], &[]),
+            (&[1, 2, 3, 4, 5], &[6, 7]),
+        ];
+        for (a, b) in pairs {
+            let (a, b) = (set_from_slice(a), set_from_slice(b));
+            let mut replica = a.clone();
+            replica.apply_diff(&a.diff(&b));
+            assert_eq!(replica, b, "{:?} -> {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_apply_diff_by_key() {
+        let before = set_from_slice(&[(1, "a"), (2, "b")]);
+        let after = set_from_slice(&[(1, "a"), (2, "B"), (3, "c")]);
+
+        let mut replica = before.clone();
+        replica.apply_diff(&before.diff_by_key(&after, |&(id, _)| id));
+        assert_eq!(replica, after);
+
+        // Same items are ignored, even when not in the set
+        replica.apply_diff(&[DiffItem::Same((9, "z"))]);
+        assert_eq!(replica, after);
+    }
+
+    /// A tagged value whose identity is its tag
+    #[derive(Debug, Clone)]
+    struct Tagged {
+        tag: char,
+        values: Vec<u32>,
+    }
+
+    impl PartialEq for Tagged {
+        fn eq(&self, other: &Self) -> bool {
+            self.tag == other.tag
+        }
+    }
+
+    impl Eq for Tagged {}
+
+    impl Hash for Tagged {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.tag.hash(state);
+        }
+    }
+
+    #[test]
+    fn test_merge_with() {
+        let tagged = |tag, values: &[u32]| Tagged { tag, values: values.to_vec() };
+        let set1: HashSet<Tagged> = [tagged('a', &[1]), tagged('b', &[2])].into_iter().collect();
+        let set2: HashSet<Tagged> = [tagged('b', &[3, 4]), tagged('c', &[5])].into_iter().collect();
+
+        let merged = set1.merge_with(set2, |mut existing, other| {
+            existing.values.extend(other.values);
+            existing
+        });
+
+        assert_eq!(merged.len(), 3);
+        let values = |tag| merged.get(&tagged(tag, &[])).unwrap().values.clone();
+        assert_eq!(values('a'), [1]);
+        // Neither colliding element won on its own
+        assert_eq!(values('b'), [2, 3, 4]);
+        assert_eq!(values('c'), [5]);
+    }
+
+    #[test]
+    fn test_merge_with_empty() {
+        let set = set_from_slice(&[1, 2]);
+        let combine = |_: i32, _: i32| panic!("no collision expected");
+        assert_eq!(set.clone().merge_with(HashSet::new(), combine), set);
+        assert_eq!(HashSet::new().merge_with(set.clone(), combine), set);
+    }
 }
@@ -5,6 +5,71 @@
 
 use std::ops::RangeInclusive;
 
+/// The maximum number of values [`MoreRangeInclusive::to_vec`] collects, about a
+/// million, to avoid running out of memory on huge ranges.
+pub const TO_VEC_MAX_LEN: usize = 1 << 20;
+
+/// A primitive integer type, for the [`MoreRangeInclusive`] methods that need integer
+/// arithmetic.
+pub trait RangeInteger: Copy + PartialOrd {
+    /// The zero value.
+    const ZERO: Self;
+
+    /// Returns the average of `self` and `other`, rounded down, without overflowing.
+    ///
+    /// Unlike the inherent `midpoint` methods of the signed integer types, which round
+    /// towards zero, the result is always below the larger argument when they differ,
+    /// which bisections rely on.
+    fn floor_midpoint(self, other: Self) -> Self;
+
+    /// Returns the value after `self`.
+    ///
+    /// # Panics
+    ///
+    /// May panic or wrap around, like `+ 1`, if `self` is the maximum value.
+    fn successor(self) -> Self;
+
+    /// Returns the value before `self`.
+    ///
+    /// # Panics
+    ///
+    /// May panic or wrap around, like `- 1`, if `self` is the minimum value.
+    fn predecessor(self) -> Self;
+
+    /// Returns `self + other`, or `None` if it overflows.
+    fn checked_add(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_range_integer {
+    ($($integer:ty),*) => {
+        $(
+            impl RangeInteger for $integer {
+                const ZERO: Self = 0;
+
+                fn floor_midpoint(self, other: Self) -> Self {
+                    // The common bits, plus half of the differing ones, with an arithmetic
+                    // shift for signed types
+                    (self & other) + ((self ^ other) >> 1)
+                }
+
+                fn successor(self) -> Self {
+                    self + 1
+                }
+
+                fn predecessor(self) -> Self {
+                    self - 1
+                }
+
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$integer>::checked_add(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_range_integer!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
 /// Extension trait for `RangeInclusive` providing additional functionality.
 ///
 /// This trait extends the standard library's `RangeInclusive` with methods for
@@ -71,7 +136,365 @@ where
     /// let range2 = 3..=7;
     /// assert_eq!(range1.intersection(&range2), Some(3..=7));
     /// ```
+    ///
+    /// # Note
+    ///
+    /// Ranges are taken as-is: an inverted range such as `5..=1` is empty and never
+    /// intersects anything. Use [`MoreRangeInclusive::intersection_normalized`] to treat
+    /// the endpoints as unordered.
     fn intersection(&self, other: &RangeInclusive<T>) -> Option<RangeInclusive<T>>;
+
+    /// Returns the range with its endpoints ordered.
+    ///
+    /// A range built from unordered endpoints, such as `5..=1`, is empty according to the
+    /// standard library. This returns `min..=max` of the two endpoints instead, so the
+    /// range covers the values between them.
+    ///
+    /// # Returns
+    ///
+    /// A `RangeInclusive<T>` whose start is not greater than its end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::MoreRangeInclusive;
+    ///
+    /// assert_eq!((5..=1).normalized(), 1..=5);
+    /// assert_eq!((1..=5).normalized(), 1..=5);
+    /// ```
+    fn normalized(&self) -> RangeInclusive<T>;
+
+    /// Finds the intersection of two ranges, treating the endpoints of each as unordered.
+    ///
+    /// This is [`MoreRangeInclusive::intersection`] on the
+    /// [normalized](MoreRangeInclusive::normalized) ranges, so an inverted range such as
+    /// `5..=1` covers the values from 1 to 5 instead of being empty.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The range to find the intersection with.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(RangeInclusive<T>)` - The intersection of the normalized ranges.
+    /// * `None` - If they do not overlap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::MoreRangeInclusive;
+    /// use std::ops::RangeInclusive;
+    ///
+    /// let range = RangeInclusive::new(5, 1);
+    /// assert_eq!(range.intersection(&(3..=7)), None);
+    /// assert_eq!(range.intersection_normalized(&RangeInclusive::new(7, 3)), Some(3..=5));
+    /// ```
+    fn intersection_normalized(&self, other: &RangeInclusive<T>) -> Option<RangeInclusive<T>> {
+        self.normalized().intersection(&other.normalized())
+    }
+
+    /// Checks whether every value of a batch falls within the range.
+    ///
+    /// The check stops at the first value outside `[start, end]`. An empty batch is
+    /// vacuously contained.
+    ///
+    /// # Parameters
+    ///
+    /// * `values` - The values to check.
+    ///
+    /// # Returns
+    ///
+    /// `true` if every value is within the range, `false` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::MoreRangeInclusive;
+    ///
+    /// let range = 1..=10;
+    /// assert!(range.contains_all([1, 5, 10]));
+    /// assert!(!range.contains_all([1, 11]));
+    /// ```
+    fn contains_all<I>(&self, values: I) -> bool
+    where
+        I: IntoIterator<Item = T>;
+
+    /// Samples a value uniformly at random within the range, both ends included.
+    ///
+    /// For integers, every value of `[start, end]` is equally likely, `end` included.
+    ///
+    /// This method is only available with the `rand` feature.
+    ///
+    /// # Parameters
+    ///
+    /// * `rng` - The random number generator to draw from.
+    ///
+    /// # Returns
+    ///
+    /// A value in `[start, end]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is empty, i.e. if `start > end`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::MoreRangeInclusive;
+    ///
+    /// let mut rng = rand::rng();
+    /// let value = (1..=6).sample(&mut rng);
+    /// assert!((1..=6).contains(&value));
+    /// ```
+    #[cfg(feature = "rand")]
+    fn sample<R>(&self, rng: &mut R) -> T
+    where
+        R: rand::Rng + ?Sized,
+        T: rand::distr::uniform::SampleUniform;
+
+    /// Collects the values of an integer range into a `Vec`.
+    ///
+    /// To avoid running out of memory on a range such as `0..=u64::MAX`, ranges of more
+    /// than [`TO_VEC_MAX_LEN`] values are refused.
+    ///
+    /// # Returns
+    ///
+    /// The values of the range, in increasing order. An empty range gives an empty `Vec`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range holds more than [`TO_VEC_MAX_LEN`] values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::MoreRangeInclusive;
+    ///
+    /// assert_eq!((1..=4).to_vec(), [1, 2, 3, 4]);
+    /// ```
+    fn to_vec(&self) -> Vec<T>
+    where
+        RangeInclusive<T>: Iterator<Item = T>;
+
+    /// Finds the smallest value of the range satisfying a monotone predicate, by bisection.
+    ///
+    /// The predicate must be `false` for the values below some point of the range, and
+    /// `true` from there on, like for `slice::partition_point`. It is called O(log n)
+    /// times. When the predicate isn't monotone, the result is one of the values it is
+    /// `true` for, not necessarily the smallest.
+    ///
+    /// # Parameters
+    ///
+    /// * `pred` - The monotone predicate.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(T)` - The smallest value of the range for which `pred` is `true`.
+    /// * `None` - If `pred` is `false` throughout the range, or the range is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::MoreRangeInclusive;
+    ///
+    /// // The smallest integer whose square reaches 1000
+    /// assert_eq!((0..=1000).partition_point(|x: i64| x * x >= 1000), Some(32));
+    /// assert_eq!((0..=10).partition_point(|x: i64| x > 10), None);
+    /// ```
+    fn partition_point<F>(&self, pred: F) -> Option<T>
+    where
+        T: RangeInteger,
+        F: Fn(T) -> bool;
+
+    /// Merges two ranges into one, when their union is contiguous.
+    ///
+    /// The union is a single range when the ranges overlap, or when they are contiguous,
+    /// like `1..=3` and `4..=6`, with no integer between them. An empty range, whose
+    /// start is after its end, doesn't add anything to the other range.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The range to merge with.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(RangeInclusive<T>)` - The range covering both ranges.
+    /// * `None` - If the ranges are disjoint, or both empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::MoreRangeInclusive;
+    ///
+    /// assert_eq!((1..=5).union(&(3..=8)), Some(1..=8));
+    /// assert_eq!((1..=5).union(&(6..=8)), Some(1..=8));
+    /// assert_eq!((1..=5).union(&(7..=8)), None);
+    /// ```
+    fn union(&self, other: &RangeInclusive<T>) -> Option<RangeInclusive<T>>
+    where
+        T: RangeInteger;
+
+    /// Merges two ranges, treating the endpoints of each as unordered.
+    ///
+    /// This is [`MoreRangeInclusive::union`] on the
+    /// [normalized](MoreRangeInclusive::normalized) ranges, so an inverted range such as
+    /// `5..=1` covers the values from 1 to 5 instead of being empty.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The range to merge with.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(RangeInclusive<T>)` - The range covering both normalized ranges.
+    /// * `None` - If they are disjoint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::MoreRangeInclusive;
+    /// use std::ops::RangeInclusive;
+    ///
+    /// let range = RangeInclusive::new(5, 1);
+    /// assert_eq!(range.union(&(3..=8)), Some(3..=8));
+    /// assert_eq!(range.union_normalized(&RangeInclusive::new(8, 3)), Some(1..=8));
+    /// ```
+    fn union_normalized(&self, other: &RangeInclusive<T>) -> Option<RangeInclusive<T>>
+    where
+        T: RangeInteger,
+    {
+        self.normalized().union(&other.normalized())
+    }
+
+    /// Computes the parts of this range not covered by another range.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The range to remove from this one.
+    ///
+    /// # Returns
+    ///
+    /// The 0, 1 or 2 ranges left, in increasing order: none when `other` covers this range
+    /// entirely, one when it clips an end or doesn't overlap, and two when it splits this
+    /// range in the middle. An empty range, whose start is after its end, leaves nothing,
+    /// and removes nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::MoreRangeInclusive;
+    ///
+    /// assert_eq!((1..=10).difference(&(4..=6)), vec![1..=3, 7..=10]);
+    /// assert_eq!((1..=10).difference(&(8..=20)), vec![1..=7]);
+    /// assert_eq!((1..=10).difference(&(0..=10)), vec![]);
+    /// ```
+    fn difference(&self, other: &RangeInclusive<T>) -> Vec<RangeInclusive<T>>
+    where
+        T: RangeInteger;
+
+    /// Checks whether this range contains every value of another range.
+    ///
+    /// An empty range, whose start is after its end, contains no range but the empty
+    /// ones, and is contained in any range.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The range to check.
+    ///
+    /// # Returns
+    ///
+    /// `true` if every value of `other` is within this range, `false` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::MoreRangeInclusive;
+    ///
+    /// assert!((1..=10).contains_range(&(3..=10)));
+    /// assert!(!(1..=10).contains_range(&(3..=11)));
+    /// ```
+    fn contains_range(&self, other: &RangeInclusive<T>) -> bool;
+
+    /// Checks whether two ranges share at least a value.
+    ///
+    /// This is equivalent to `self.intersection(other).is_some()`, without building the
+    /// intersection. Touching ranges, sharing an endpoint, overlap, while empty ranges
+    /// overlap nothing.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The range to check.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the ranges overlap, `false` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::MoreRangeInclusive;
+    ///
+    /// assert!((1..=5).overlaps(&(5..=8)));
+    /// assert!(!(1..=5).overlaps(&(6..=8)));
+    /// ```
+    fn overlaps(&self, other: &RangeInclusive<T>) -> bool;
+
+    /// Returns the open gap between two disjoint ranges.
+    ///
+    /// The gap is returned as the range from the end of the lower range to the start of
+    /// the upper one, and is exclusive at both ends: its bounds belong to the ranges, and
+    /// the gap is the values strictly between them. This works with any ordered values,
+    /// like float time windows. For integers, see [`MoreRangeInclusive::integer_gap`],
+    /// which returns the values of the gap themselves.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The range to compare with, before or after this one.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(RangeInclusive<T>)` - The bounds of the gap, excluded from it, in increasing
+    ///   order.
+    /// * `None` - If the ranges overlap or touch, or if either is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::MoreRangeInclusive;
+    ///
+    /// assert_eq!((0.0..=1.5).gap(&(2.5..=4.0)), Some(1.5..=2.5));
+    /// assert_eq!((7..=9).gap(&(1..=3)), Some(3..=7));
+    /// assert_eq!((1..=3).gap(&(3..=9)), None);
+    /// ```
+    fn gap(&self, other: &RangeInclusive<T>) -> Option<RangeInclusive<T>>;
+
+    /// Returns the integers between two disjoint integer ranges.
+    ///
+    /// Unlike [`MoreRangeInclusive::gap`], the result holds the values after the end of
+    /// the lower range and before the start of the upper one, so it shares no value with
+    /// either range. Contiguous ranges, like `1..=3` and `4..=6`, leave no gap.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The range to compare with, before or after this one.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(RangeInclusive<T>)` - The integers between the ranges, never empty.
+    /// * `None` - If the ranges overlap or are contiguous, or if either is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::MoreRangeInclusive;
+    ///
+    /// assert_eq!((1..=3).integer_gap(&(7..=9)), Some(4..=6));
+    /// assert_eq!((7..=9).integer_gap(&(1..=3)), Some(4..=6));
+    /// assert_eq!((1..=3).integer_gap(&(4..=9)), None);
+    /// ```
+    fn integer_gap(&self, other: &RangeInclusive<T>) -> Option<RangeInclusive<T>>
+    where
+        T: RangeInteger;
 }
 
 impl<T> MoreRangeInclusive<T> for RangeInclusive<T>
@@ -92,6 +515,208 @@ where
             None
         }
     }
+
+    fn normalized(&self) -> RangeInclusive<T> {
+        if self.start() > self.end() {
+            *self.end()..=*self.start()
+        } else {
+            *self.start()..=*self.end()
+        }
+    }
+
+    fn contains_all<I>(&self, values: I) -> bool
+    where
+        I: IntoIterator<Item = T>,
+    {
+        values.into_iter().all(|value| self.contains(&value))
+    }
+
+    #[cfg(feature = "rand")]
+    fn sample<R>(&self, rng: &mut R) -> T
+    where
+        R: rand::Rng + ?Sized,
+        T: rand::distr::uniform::SampleUniform,
+    {
+        // Sampling from the inclusive range keeps `end` reachable
+        rng.random_range(*self.start()..=*self.end())
+    }
+
+    fn to_vec(&self) -> Vec<T>
+    where
+        RangeInclusive<T>: Iterator<Item = T>,
+    {
+        // The size hint of integer ranges is exact, and saturates when the length overflows
+        let (len, _) = self.clone().size_hint();
+        assert!(
+            len <= TO_VEC_MAX_LEN,
+            "range of {len} values is too large to collect, the maximum is {TO_VEC_MAX_LEN}"
+        );
+        self.clone().collect()
+    }
+
+    fn partition_point<F>(&self, pred: F) -> Option<T>
+    where
+        T: RangeInteger,
+        F: Fn(T) -> bool,
+    {
+        let (mut low, mut high) = (*self.start(), *self.end());
+        if low > high || !pred(high) {
+            return None;
+        }
+
+        // The answer stays within `low..=high`, and `mid < high` ensures progress
+        while low < high {
+            let mid = low.floor_midpoint(high);
+            if pred(mid) {
+                high = mid;
+            } else {
+                low = mid.successor();
+            }
+        }
+        Some(low)
+    }
+
+    fn union(&self, other: &RangeInclusive<T>) -> Option<RangeInclusive<T>>
+    where
+        T: RangeInteger,
+    {
+        match (self.is_empty(), other.is_empty()) {
+            (true, true) => return None,
+            (true, false) => return Some(other.clone()),
+            (false, true) => return Some(self.clone()),
+            (false, false) => {}
+        }
+
+        // Ranges merge into a single one when they overlap, or when one starts right after
+        // the other ends. The lower end is below a start, so its successor can't overflow.
+        let contiguous = |lower: &RangeInclusive<T>, upper: &RangeInclusive<T>| {
+            lower.end() < upper.start() && lower.end().successor() == *upper.start()
+        };
+        let overlapping = self.intersection(other).is_some();
+        if !overlapping && !contiguous(self, other) && !contiguous(other, self) {
+            return None;
+        }
+        let start = if self.start() < other.start() { *self.start() } else { *other.start() };
+        let end = if self.end() > other.end() { *self.end() } else { *other.end() };
+        Some(start..=end)
+    }
+
+    fn difference(&self, other: &RangeInclusive<T>) -> Vec<RangeInclusive<T>>
+    where
+        T: RangeInteger,
+    {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        if self.intersection(other).is_none() {
+            return vec![self.clone()];
+        }
+
+        // `other` overlaps this range, so its bounds can be stepped over without overflowing
+        let mut parts = Vec::with_capacity(2);
+        if other.start() > self.start() {
+            parts.push(*self.start()..=other.start().predecessor());
+        }
+        if other.end() < self.end() {
+            parts.push(other.end().successor()..=*self.end());
+        }
+        parts
+    }
+
+    fn contains_range(&self, other: &RangeInclusive<T>) -> bool {
+        if other.is_empty() {
+            return true;
+        }
+        !self.is_empty()
+            && self.start() <= other.start()
+            && other.end() <= self.end()
+    }
+
+    fn overlaps(&self, other: &RangeInclusive<T>) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.start() <= other.end()
+            && other.start() <= self.end()
+    }
+
+    fn gap(&self, other: &RangeInclusive<T>) -> Option<RangeInclusive<T>> {
+        if self.is_empty() || other.is_empty() {
+            return None;
+        }
+        if self.end() < other.start() {
+            Some(*self.end()..=*other.start())
+        } else if other.end() < self.start() {
+            Some(*other.end()..=*self.start())
+        } else {
+            None
+        }
+    }
+
+    fn integer_gap(&self, other: &RangeInclusive<T>) -> Option<RangeInclusive<T>>
+    where
+        T: RangeInteger,
+    {
+        // The open gap's bounds are in increasing order, so neither step can overflow
+        let open = self.gap(other)?;
+        let gap = open.start().successor()..=open.end().predecessor();
+        (!gap.is_empty()).then_some(gap)
+    }
+}
+
+/// Extension of [`MoreRangeInclusive`] for integer ranges that can be iterated by steps.
+///
+/// It's implemented for the ranges of every [`RangeInteger`].
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::collections::more_range::SteppableRange;
+///
+/// let values: Vec<u32> = (0..=20).step_by_checked(5).collect();
+/// assert_eq!(values, vec![0, 5, 10, 15, 20]);
+/// ```
+pub trait SteppableRange<T>: MoreRangeInclusive<T>
+where
+    T: Copy + PartialOrd,
+{
+    /// Returns an iterator over every `step`-th value of the range.
+    ///
+    /// The iterator yields `start`, `start + step`, `start + 2 * step`, and so on, as long
+    /// as the value doesn't exceed `end`: `end` itself is only yielded when a step lands
+    /// on it. The iteration stops instead of overflowing near the maximum value of `T`.
+    /// An empty range, whose start is after its end, yields nothing.
+    ///
+    /// # Parameters
+    ///
+    /// * `step` - The difference between two consecutive values. Must be positive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is zero or negative.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_range::SteppableRange;
+    ///
+    /// assert_eq!((1..=10).step_by_checked(3).collect::<Vec<_>>(), vec![1, 4, 7, 10]);
+    /// assert_eq!((1..=10).step_by_checked(4).collect::<Vec<_>>(), vec![1, 5, 9]);
+    /// ```
+    fn step_by_checked(&self, step: T) -> impl Iterator<Item = T>;
+}
+
+impl<T> SteppableRange<T> for RangeInclusive<T>
+where
+    T: RangeInteger,
+{
+    fn step_by_checked(&self, step: T) -> impl Iterator<Item = T> {
+        assert!(step > T::ZERO, "step must be positive");
+        let end = *self.end();
+        let first = (self.start() <= self.end()).then_some(*self.start());
+        std::iter::successors(first, move |value| {
+            value.checked_add(step).filter(|next| *next <= end)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -167,4 +792,285 @@ mod tests {
         let range2 = 'd'..='f';
         assert_eq!(range1.intersection(&range2), None);
     }
+
+    #[test]
+    fn test_normalized() {
+        assert_eq!(RangeInclusive::new(5, 1).normalized(), 1..=5);
+        assert_eq!((1..=5).normalized(), 1..=5);
+        assert_eq!((3..=3).normalized(), 3..=3);
+        assert_eq!(RangeInclusive::new(2.5, -1.0).normalized(), -1.0..=2.5);
+    }
+
+    #[test]
+    fn test_intersection_inverted() {
+        // Inverted ranges are empty, so they never intersect anything
+        assert_eq!(RangeInclusive::new(5, 1).intersection(&(0..=10)), None);
+
+        // Normalizing first treats the endpoints as unordered
+        let range1 = RangeInclusive::new(5, 1);
+        let range2 = RangeInclusive::new(7, 3);
+        assert_eq!(range1.normalized().intersection(&range2.normalized()), Some(3..=5));
+        assert_eq!(range1.intersection_normalized(&range2), Some(3..=5));
+        assert_eq!(range1.intersection_normalized(&(0..=2)), Some(1..=2));
+        assert_eq!(range1.intersection_normalized(&RangeInclusive::new(9, 6)), None);
+    }
+
+    #[test]
+    fn test_contains_all() {
+        let range = 1..=10;
+        assert!(range.contains_all([1, 4, 7, 10]));
+        assert!(!range.contains_all([1, 4, 11, 7]));
+        assert!(range.contains_all(Vec::<i32>::new()));
+
+        let range = 0.0..=1.0;
+        assert!(range.contains_all([0.0, 0.5, 1.0]));
+        assert!(!range.contains_all([0.5, -0.1]));
+    }
+
+    #[test]
+    fn test_contains_all_short_circuits() {
+        let mut checked = 0;
+        let values = [1, 20, 3, 4].into_iter().inspect(|_| checked += 1);
+        assert!(!(1..=10).contains_all(values));
+        assert_eq!(checked, 2);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_sample_integers() {
+        use rand::SeedableRng;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let range = 1..=6;
+        let mut seen = [false; 6];
+        for _ in 0..1_000 {
+            let value = range.sample(&mut rng);
+            assert!(range.contains(&value));
+            seen[value as usize - 1] = true;
+        }
+        // Both endpoints, and everything in between, come up
+        assert!(seen.iter().all(|&s| s));
+
+        let single = 3..=3;
+        assert_eq!(single.sample(&mut rng), 3);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_sample_floats() {
+        use rand::SeedableRng;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let range = -1.5..=2.5;
+        let (mut below_middle, mut above_middle) = (false, false);
+        for _ in 0..1_000 {
+            let value = range.sample(&mut rng);
+            assert!(range.contains(&value));
+            below_middle |= value < 0.5;
+            above_middle |= value > 0.5;
+        }
+        assert!(below_middle && above_middle);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    #[should_panic]
+    fn test_sample_empty_range() {
+        let mut rng = rand::rng();
+        RangeInclusive::new(5, 1).sample(&mut rng);
+    }
+
+    #[test]
+    fn test_to_vec() {
+        assert_eq!((1..=5).to_vec(), [1, 2, 3, 4, 5]);
+        assert_eq!((-2i8..=1).to_vec(), [-2, -1, 0, 1]);
+        assert_eq!((7u64..=7).to_vec(), [7]);
+        assert!(RangeInclusive::new(5, 1).to_vec().is_empty());
+
+        let at_limit = 0..=(TO_VEC_MAX_LEN - 1);
+        assert_eq!(at_limit.to_vec().len(), TO_VEC_MAX_LEN);
+    }
+
+    #[test]
+    #[should_panic(expected = "too large to collect")]
+    fn test_to_vec_huge_range() {
+        (0..=u64::MAX).to_vec();
+    }
+
+    #[test]
+    fn test_partition_point() {
+        // True only at the end
+        assert_eq!((1..=100).partition_point(|x: i32| x == 100), Some(100));
+        // True throughout
+        assert_eq!((1..=100).partition_point(|_: i32| true), Some(1));
+        // Never true
+        assert_eq!((1..=100).partition_point(|_: i32| false), None);
+
+        assert_eq!((-50..=50).partition_point(|x: i8| x >= -7), Some(-7));
+        assert_eq!((5..=5).partition_point(|x: u8| x >= 5), Some(5));
+        assert_eq!(RangeInclusive::new(5, 1).partition_point(|_: u8| true), None);
+    }
+
+    #[test]
+    fn test_partition_point_extreme_bounds() {
+        let calls = std::cell::Cell::new(0);
+        let first = (i64::MIN..=i64::MAX).partition_point(|x| {
+            calls.set(calls.get() + 1);
+            x >= i64::MAX - 1
+        });
+        assert_eq!(first, Some(i64::MAX - 1));
+        assert!(calls.get() <= 65);
+
+        assert_eq!((0..=u128::MAX).partition_point(|x| x > u128::MAX / 3), Some(u128::MAX / 3 + 1));
+        assert_eq!((i128::MIN..=i128::MIN + 1).partition_point(|x| x > i128::MIN), Some(i128::MIN + 1));
+    }
+
+    #[test]
+    fn test_floor_midpoint() {
+        assert_eq!(3i32.floor_midpoint(6), 4);
+        assert_eq!((-3i32).floor_midpoint(-2), -3);
+        assert_eq!(i8::MIN.floor_midpoint(i8::MAX), -1);
+        assert_eq!(u8::MAX.floor_midpoint(u8::MAX - 1), u8::MAX - 1);
+    }
+
+    #[test]
+    fn test_union() {
+        assert_eq!((1..=5).union(&(3..=8)), Some(1..=8));
+        assert_eq!((3..=8).union(&(1..=5)), Some(1..=8));
+        // Touching ranges share an endpoint
+        assert_eq!((1..=5).union(&(5..=8)), Some(1..=8));
+        assert_eq!((2..=4).union(&(1..=9)), Some(1..=9));
+        // Contiguous ranges have no integer between them
+        assert_eq!((1..=3).union(&(4..=6)), Some(1..=6));
+        assert_eq!((4..=6).union(&(1..=3)), Some(1..=6));
+        // Disjoint ranges
+        assert_eq!((1..=3).union(&(5..=6)), None);
+        assert_eq!((5..=6).union(&(1..=3)), None);
+
+        // Empty ranges
+        assert_eq!(RangeInclusive::new(5, 1).union(&(7..=8)), Some(7..=8));
+        assert_eq!((7..=8).union(&RangeInclusive::new(5, 1)), Some(7..=8));
+        assert_eq!(RangeInclusive::new(5, 1).union(&RangeInclusive::new(9, 2)), None);
+
+        // Normalized, the inverted ranges cover values
+        assert_eq!(RangeInclusive::new(5, 1).union_normalized(&(7..=8)), None);
+        assert_eq!(RangeInclusive::new(5, 1).union_normalized(&RangeInclusive::new(9, 2)), Some(1..=9));
+    }
+
+    #[test]
+    fn test_union_extreme_bounds() {
+        assert_eq!((0..=u8::MAX).union(&(u8::MAX..=u8::MAX)), Some(0..=u8::MAX));
+        assert_eq!((0..=9u8).union(&(10..=u8::MAX)), Some(0..=u8::MAX));
+        assert_eq!((i8::MIN..=-1).union(&(0..=i8::MAX)), Some(i8::MIN..=i8::MAX));
+    }
+
+    #[test]
+    fn test_difference() {
+        // Split in the middle
+        assert_eq!((1..=10).difference(&(4..=6)), vec![1..=3, 7..=10]);
+        // Clipped ends
+        assert_eq!((1..=10).difference(&(8..=20)), vec![1..=7]);
+        assert_eq!((1..=10).difference(&(-5..=1)), vec![2..=10]);
+        // Fully covered
+        assert_eq!((1..=10).difference(&(1..=10)), vec![]);
+        assert_eq!((3..=4).difference(&(0..=10)), vec![]);
+        // Disjoint or empty
+        assert_eq!((1..=10).difference(&(11..=20)), vec![1..=10]);
+        assert_eq!((1..=10).difference(&RangeInclusive::new(6, 4)), vec![1..=10]);
+        assert_eq!(RangeInclusive::new(6, 4).difference(&(1..=10)), vec![]);
+    }
+
+    #[test]
+    fn test_difference_extreme_bounds() {
+        assert_eq!((0..=u8::MAX).difference(&(1..=254)), vec![0..=0, 255..=255]);
+        assert_eq!((i8::MIN..=i8::MAX).difference(&(i8::MIN..=0)), vec![1..=i8::MAX]);
+    }
+
+    #[test]
+    fn test_contains_range() {
+        assert!((1..=10).contains_range(&(1..=10)));
+        assert!((1..=10).contains_range(&(4..=6)));
+        assert!(!(1..=10).contains_range(&(0..=5)));
+        assert!(!(1..=10).contains_range(&(11..=12)));
+        assert!((0.0..=1.0).contains_range(&(0.25..=0.75)));
+
+        // Empty ranges
+        assert!((1..=10).contains_range(&RangeInclusive::new(20, 15)));
+        assert!(RangeInclusive::new(10, 1).contains_range(&RangeInclusive::new(6, 4)));
+        assert!(!RangeInclusive::new(10, 1).contains_range(&(4..=6)));
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let ranges = [1..=5, 5..=8, 6..=8, 2..=3, 0..=10, RangeInclusive::new(4, 2)];
+        for a in &ranges {
+            for b in &ranges {
+                assert_eq!(a.overlaps(b), a.intersection(b).is_some(), "{:?} {:?}", a, b);
+                assert_eq!(a.overlaps(b), b.overlaps(a), "{:?} {:?}", a, b);
+            }
+        }
+        assert!(!RangeInclusive::new(8, 2).overlaps(&(0..=10)));
+        assert!(!(0.0..=1.0).overlaps(&(f64::NAN..=2.0)));
+    }
+
+    #[test]
+    fn test_gap() {
+        assert_eq!((1..=3).gap(&(7..=9)), Some(3..=7));
+        assert_eq!((7..=9).gap(&(1..=3)), Some(3..=7));
+        assert_eq!((0.0..=1.0).gap(&(1.5..=2.0)), Some(1.0..=1.5));
+        // Adjacent integers leave an open gap without values
+        assert_eq!((1..=3).gap(&(4..=6)), Some(3..=4));
+
+        // Overlapping or touching
+        assert_eq!((1..=3).gap(&(3..=9)), None);
+        assert_eq!((1..=5).gap(&(2..=3)), None);
+        // Empty ranges
+        assert_eq!((1..=3).gap(&RangeInclusive::new(9, 7)), None);
+        assert_eq!(RangeInclusive::new(3, 1).gap(&(7..=9)), None);
+    }
+
+    #[test]
+    fn test_integer_gap() {
+        assert_eq!((1..=3).integer_gap(&(7..=9)), Some(4..=6));
+        assert_eq!((7..=9).integer_gap(&(1..=3)), Some(4..=6));
+        assert_eq!((1..=3).integer_gap(&(5..=6)), Some(4..=4));
+        assert_eq!((0..=0u8).integer_gap(&(u8::MAX..=u8::MAX)), Some(1..=u8::MAX - 1));
+
+        // Contiguous, overlapping or touching
+        assert_eq!((1..=3).integer_gap(&(4..=6)), None);
+        assert_eq!((4..=6).integer_gap(&(1..=3)), None);
+        assert_eq!((1..=3).integer_gap(&(3..=9)), None);
+        assert_eq!((1..=5).integer_gap(&(2..=3)), None);
+        // Empty ranges
+        assert_eq!((1..=3).integer_gap(&RangeInclusive::new(9, 7)), None);
+    }
+
+    #[test]
+    fn test_step_by_checked() {
+        assert_eq!((1..=10).step_by_checked(3).collect::<Vec<_>>(), vec![1, 4, 7, 10]);
+        assert_eq!((1..=10).step_by_checked(20).collect::<Vec<_>>(), vec![1]);
+        assert_eq!((-6..=6).step_by_checked(4).collect::<Vec<_>>(), vec![-6, -2, 2, 6]);
+        assert_eq!((5..=5).step_by_checked(1).collect::<Vec<_>>(), vec![5]);
+        assert_eq!(RangeInclusive::new(5, 1).step_by_checked(1).count(), 0);
+    }
+
+    #[test]
+    fn test_step_by_checked_extreme_bounds() {
+        // Stops instead of overflowing past the maximum value
+        assert_eq!((250..=u8::MAX).step_by_checked(3).collect::<Vec<_>>(), vec![250, 253]);
+        assert_eq!((0..=u8::MAX).step_by_checked(u8::MAX).collect::<Vec<_>>(), vec![0, 255]);
+        assert_eq!((i64::MIN..=i64::MAX).step_by_checked(i64::MAX).count(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "step must be positive")]
+    fn test_step_by_checked_zero_step() {
+        let _ = (1..=10).step_by_checked(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "step must be positive")]
+    fn test_step_by_checked_negative_step() {
+        let _ = (1..=10).step_by_checked(-1);
+    }
 }
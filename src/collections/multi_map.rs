@@ -0,0 +1,345 @@
+//! A map associating each key with several values.
+//!
+//! This module provides the `MultiMap` struct, which replaces hand-rolled
+//! `HashMap<K, Vec<V>>`s, and the `MultiMapStorage` trait abstracting over the map
+//! storing the lists of values, so keys that can't be hashed can use a `VecMap` instead.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use crate::collections::vec_map::VecMap;
+
+/// A map storing the list of values of each key of a [`MultiMap`].
+///
+/// It's implemented for `HashMap<K, Vec<V>>`, for hashable keys, and for
+/// `VecMap<K, Vec<V>>`, for keys that only implement `PartialEq`.
+pub trait MultiMapStorage<K, V>: Default {
+    /// Returns the values of `key`, if it's in the map.
+    fn values(&self, key: &K) -> Option<&Vec<V>>;
+
+    /// Returns the values of `key` to modify them, if it's in the map.
+    fn values_mut(&mut self, key: &K) -> Option<&mut Vec<V>>;
+
+    /// Returns the values of `key` to modify them, inserting an empty list first if the
+    /// key isn't in the map.
+    fn values_or_insert(&mut self, key: K) -> &mut Vec<V>;
+
+    /// Removes `key` from the map, returning its values.
+    fn remove_key(&mut self, key: &K) -> Option<Vec<V>>;
+
+    /// Returns an iterator over the keys and their values.
+    fn iter_keys<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a Vec<V>)>
+    where
+        K: 'a,
+        V: 'a;
+}
+
+impl<K, V> MultiMapStorage<K, V> for HashMap<K, Vec<V>>
+where
+    K: Eq + Hash,
+{
+    fn values(&self, key: &K) -> Option<&Vec<V>> {
+        self.get(key)
+    }
+
+    fn values_mut(&mut self, key: &K) -> Option<&mut Vec<V>> {
+        self.get_mut(key)
+    }
+
+    fn values_or_insert(&mut self, key: K) -> &mut Vec<V> {
+        self.entry(key).or_default()
+    }
+
+    fn remove_key(&mut self, key: &K) -> Option<Vec<V>> {
+        self.remove(key)
+    }
+
+    fn iter_keys<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a Vec<V>)>
+    where
+        K: 'a,
+        V: 'a,
+    {
+        self.iter()
+    }
+}
+
+impl<K, V> MultiMapStorage<K, V> for VecMap<K, Vec<V>>
+where
+    K: PartialEq,
+{
+    fn values(&self, key: &K) -> Option<&Vec<V>> {
+        self.get(key)
+    }
+
+    fn values_mut(&mut self, key: &K) -> Option<&mut Vec<V>> {
+        self.get_mut(key)
+    }
+
+    fn values_or_insert(&mut self, key: K) -> &mut Vec<V> {
+        self.entry(key).or_insert_with(Vec::new)
+    }
+
+    fn remove_key(&mut self, key: &K) -> Option<Vec<V>> {
+        self.remove(key)
+    }
+
+    fn iter_keys<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a Vec<V>)>
+    where
+        K: 'a,
+        V: 'a,
+    {
+        self.iter().map(|(key, values)| (key, values))
+    }
+}
+
+/// A map associating each key with a list of values.
+///
+/// Values are kept in insertion order within each key, duplicates included. A key is
+/// only in the map while it has at least one value.
+///
+/// # Type Parameters
+///
+/// * `K` - The type of the keys.
+/// * `V` - The type of the values.
+/// * `S` - The map storing the values of each key, a `HashMap` by default. See
+///   [`VecMultiMap`] for keys that can't be hashed.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::collections::multi_map::MultiMap;
+///
+/// let mut tags = MultiMap::new();
+/// tags.insert("track-1", "drums");
+/// tags.insert("track-1", "loop");
+/// tags.insert("track-2", "vocals");
+///
+/// assert_eq!(tags.get(&"track-1"), ["drums", "loop"]);
+/// assert!(tags.get(&"track-3").is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct MultiMap<K, V, S = HashMap<K, Vec<V>>> {
+    /// The values of each key, never empty
+    storage: S,
+    /// The key and value types, only used through `S`
+    _marker: PhantomData<(K, V)>,
+}
+
+/// A [`MultiMap`] backed by a [`VecMap`], for keys that only implement `PartialEq`.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::collections::multi_map::VecMultiMap;
+///
+/// let mut map = VecMultiMap::default();
+/// map.insert(0.5, "half");
+/// map.insert(0.5, "50%");
+/// assert_eq!(map.get(&0.5), ["half", "50%"]);
+/// ```
+pub type VecMultiMap<K, V> = MultiMap<K, V, VecMap<K, Vec<V>>>;
+
+impl<K, V> MultiMap<K, V>
+where
+    K: Eq + Hash,
+{
+    /// Creates a new, empty `MultiMap` backed by a `HashMap`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K, V, S> MultiMap<K, V, S>
+where
+    S: MultiMapStorage<K, V>,
+{
+    /// Appends a value to the values of a key.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - The key to add a value to.
+    /// * `value` - The value to add, after the existing values of `key`.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.storage.values_or_insert(key).push(value);
+    }
+
+    /// Returns the values of a key, in insertion order.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - The key to look up.
+    ///
+    /// # Returns
+    ///
+    /// The values of `key`, or an empty slice if the map doesn't contain `key`.
+    pub fn get(&self, key: &K) -> &[V] {
+        self.storage.values(key).map_or(&[], Vec::as_slice)
+    }
+
+    /// Checks whether the map contains at least a value for `key`.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.storage.values(key).is_some()
+    }
+
+    /// Removes a key and all its values.
+    ///
+    /// # Returns
+    ///
+    /// The values that were associated with `key`, empty if there were none.
+    pub fn remove(&mut self, key: &K) -> Vec<V> {
+        self.storage.remove_key(key).unwrap_or_default()
+    }
+
+    /// Removes the first occurrence of a value from the values of a key.
+    ///
+    /// The key is removed along with its last value.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - The key to remove a value from.
+    /// * `value` - The value to remove.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the value was found and removed, `false` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::multi_map::MultiMap;
+    ///
+    /// let mut map = MultiMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(1, "b");
+    ///
+    /// assert!(map.remove_value(&1, &"a"));
+    /// assert!(!map.remove_value(&1, &"a"));
+    /// assert_eq!(map.get(&1), ["b"]);
+    /// ```
+    pub fn remove_value(&mut self, key: &K, value: &V) -> bool
+    where
+        V: PartialEq,
+    {
+        let Some(values) = self.storage.values_mut(key) else {
+            return false;
+        };
+        let Some(index) = values.iter().position(|v| v == value) else {
+            return false;
+        };
+        values.remove(index);
+        if values.is_empty() {
+            self.storage.remove_key(key);
+        }
+        true
+    }
+
+    /// Returns an iterator over every key-value pair, each key being repeated for each
+    /// of its values.
+    ///
+    /// The values of a key are yielded in insertion order. The order of the keys is the
+    /// one of the storage: arbitrary for a `HashMap`, insertion order for a `VecMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::multi_map::VecMultiMap;
+    ///
+    /// let mut map = VecMultiMap::default();
+    /// map.insert("a", 1);
+    /// map.insert("b", 2);
+    /// map.insert("a", 3);
+    ///
+    /// let pairs: Vec<_> = map.iter_all().collect();
+    /// assert_eq!(pairs, [(&"a", &1), (&"a", &3), (&"b", &2)]);
+    /// ```
+    pub fn iter_all(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.storage.iter_keys()
+            .flat_map(|(key, values)| values.iter().map(move |value| (key, value)))
+    }
+
+    /// Checks whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.storage.iter_keys().next().is_none()
+    }
+}
+
+impl<K, V, S> Default for MultiMap<K, V, S>
+where
+    S: Default,
+{
+    fn default() -> Self {
+        Self { storage: S::default(), _marker: PhantomData }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiple_values_per_key() {
+        let mut map = MultiMap::new();
+        map.insert("a", 1);
+        map.insert("b", 2);
+        map.insert("a", 3);
+        map.insert("a", 1);
+
+        assert_eq!(map.get(&"a"), [1, 3, 1]);
+        assert_eq!(map.get(&"b"), [2]);
+        assert_eq!(map.get(&"c"), [] as [i32; 0]);
+        assert!(map.contains_key(&"a"));
+        assert!(!map.contains_key(&"c"));
+
+        let mut pairs: Vec<_> = map.iter_all().collect();
+        pairs.sort();
+        assert_eq!(pairs, [(&"a", &1), (&"a", &1), (&"a", &3), (&"b", &2)]);
+    }
+
+    #[test]
+    fn test_remove_value() {
+        let mut map = MultiMap::new();
+        map.insert(1, "x");
+        map.insert(1, "y");
+        map.insert(1, "x");
+
+        // Only the first occurrence is removed
+        assert!(map.remove_value(&1, &"x"));
+        assert_eq!(map.get(&1), ["y", "x"]);
+        assert!(!map.remove_value(&1, &"z"));
+        assert!(!map.remove_value(&2, &"x"));
+
+        // The key goes away with its last value
+        assert!(map.remove_value(&1, &"y"));
+        assert!(map.remove_value(&1, &"x"));
+        assert!(!map.contains_key(&1));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut map = MultiMap::new();
+        map.insert('k', 1);
+        map.insert('k', 2);
+
+        assert_eq!(map.remove(&'k'), [1, 2]);
+        assert_eq!(map.remove(&'k'), Vec::<i32>::new());
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_vec_map_storage() {
+        // `f64` keys can't be hashed
+        let mut map = VecMultiMap::default();
+        map.insert(2.5, "b");
+        map.insert(1.5, "a");
+        map.insert(2.5, "c");
+
+        assert_eq!(map.get(&2.5), ["b", "c"]);
+        assert_eq!(map.iter_all().collect::<Vec<_>>(), [(&2.5, &"b"), (&2.5, &"c"), (&1.5, &"a")]);
+
+        assert!(map.remove_value(&1.5, &"a"));
+        assert!(!map.contains_key(&1.5));
+        assert_eq!(map.iter_all().count(), 2);
+    }
+}
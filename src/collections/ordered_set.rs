@@ -0,0 +1,245 @@
+//! A set iterating in insertion order.
+//!
+//! This module provides the `OrderedSet` struct, a set with the deduplication of a
+//! `HashSet` and the deterministic iteration order of a `Vec`.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::collections::more_hashset::DiffItem;
+
+/// A set that iterates over its elements in the order they were first inserted.
+///
+/// Membership is checked with a `HashSet`, while a `Vec` keeps the order, so every
+/// element is stored twice: this is only meant for cheaply cloned elements. Inserting
+/// and checking membership take constant time, removing takes linear time.
+///
+/// Removing an element and inserting it again moves it to the end.
+///
+/// # Type Parameters
+///
+/// * `T` - The type of elements in the set. Must implement `Eq`, `Hash`, and `Clone`.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::collections::ordered_set::OrderedSet;
+///
+/// let mut set = OrderedSet::new();
+/// set.insert("c");
+/// set.insert("a");
+/// set.insert("b");
+/// assert!(!set.insert("c")); // Already present, keeps its place
+///
+/// set.remove(&"a");
+/// assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec!["c", "b"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct OrderedSet<T> {
+    /// The elements of the set, for membership checks
+    members: HashSet<T>,
+    /// The elements of the set, in insertion order
+    order: Vec<T>,
+}
+
+impl<T> OrderedSet<T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Creates a new, empty `OrderedSet`.
+    pub fn new() -> Self {
+        Self { members: HashSet::new(), order: Vec::new() }
+    }
+
+    /// Adds a value to the end of the set, unless it is already present.
+    ///
+    /// # Parameters
+    ///
+    /// * `value` - The value to add.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the value was newly added, `false` if it was already present, in which
+    /// case it keeps its place.
+    pub fn insert(&mut self, value: T) -> bool {
+        if !self.members.insert(value.clone()) {
+            return false;
+        }
+        self.order.push(value);
+        true
+    }
+
+    /// Checks whether the set contains a value.
+    pub fn contains(&self, value: &T) -> bool {
+        self.members.contains(value)
+    }
+
+    /// Removes a value from the set, keeping the order of the other elements.
+    ///
+    /// # Parameters
+    ///
+    /// * `value` - The value to remove.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the value was present, `false` otherwise.
+    pub fn remove(&mut self, value: &T) -> bool {
+        if !self.members.remove(value) {
+            return false;
+        }
+        self.order.retain(|v| v != value);
+        true
+    }
+
+    /// Returns an iterator over the elements of the set, in insertion order.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.order.iter()
+    }
+
+    /// Returns the number of elements in the set.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Checks whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Compares two sets and returns a vector of `DiffItem`s representing the differences,
+    /// like [`MoreHashSet::diff`](super::more_hashset::MoreHashSet::diff), but in a
+    /// deterministic order.
+    ///
+    /// The elements of this set come first, in its order, as `Same` or `Removed`. Then
+    /// come the elements only present in the other set, in its order, as `Added`.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The set to compare with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::more_hashset::DiffItem;
+    /// use cutoff_common::collections::ordered_set::OrderedSet;
+    ///
+    /// let before: OrderedSet<_> = [1, 2, 3].into_iter().collect();
+    /// let after: OrderedSet<_> = [4, 3, 2].into_iter().collect();
+    ///
+    /// assert_eq!(before.diff(&after), vec![
+    ///     DiffItem::Removed(1),
+    ///     DiffItem::Same(2),
+    ///     DiffItem::Same(3),
+    ///     DiffItem::Added(4),
+    /// ]);
+    /// ```
+    pub fn diff(&self, other: &OrderedSet<T>) -> Vec<DiffItem<T>> {
+        self.order.iter()
+            .map(|item| if other.contains(item) {
+                DiffItem::Same(item.clone())
+            } else {
+                DiffItem::Removed(item.clone())
+            })
+            .chain(other.order.iter()
+                .filter(|item| !self.contains(item))
+                .map(|item| DiffItem::Added(item.clone()))
+            )
+            .collect()
+    }
+}
+
+impl<T> Default for OrderedSet<T>
+where
+    T: Eq + Hash + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Extend<T> for OrderedSet<T>
+where
+    T: Eq + Hash + Clone,
+{
+    /// Inserts every value of the iterator, in order, skipping those already present.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for OrderedSet<T>
+where
+    T: Eq + Hash + Clone,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a, T> IntoIterator for &'a OrderedSet<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.order.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_vec<T: Clone>(set: &OrderedSet<T>) -> Vec<T> {
+        set.order.to_vec()
+    }
+
+    #[test]
+    fn test_insertion_order() {
+        let mut set = OrderedSet::new();
+        for value in [5, 1, 4, 1, 5, 9, 2, 6] {
+            set.insert(value);
+        }
+        assert_eq!(to_vec(&set), vec![5, 1, 4, 9, 2, 6]);
+        assert_eq!(set.len(), 6);
+        assert!(set.contains(&9));
+        assert!(!set.contains(&3));
+    }
+
+    #[test]
+    fn test_order_after_removals() {
+        let mut set: OrderedSet<_> = ["a", "b", "c", "d"].into_iter().collect();
+
+        assert!(set.remove(&"b"));
+        assert!(!set.remove(&"b"));
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec!["a", "c", "d"]);
+
+        // A removed element is inserted again at the end
+        assert!(set.insert("b"));
+        assert!(set.remove(&"a"));
+        assert_eq!((&set).into_iter().copied().collect::<Vec<_>>(), vec!["c", "d", "b"]);
+
+        assert!(set.remove(&"c") && set.remove(&"d") && set.remove(&"b"));
+        assert!(set.is_empty());
+        assert_eq!(set.members.len(), 0);
+    }
+
+    #[test]
+    fn test_diff() {
+        let before: OrderedSet<_> = [3, 1, 2].into_iter().collect();
+        let after: OrderedSet<_> = [5, 2, 4, 3].into_iter().collect();
+
+        assert_eq!(before.diff(&after), vec![
+            DiffItem::Same(3),
+            DiffItem::Removed(1),
+            DiffItem::Same(2),
+            DiffItem::Added(5),
+            DiffItem::Added(4),
+        ]);
+        assert!(before.diff(&before).iter().all(|item| matches!(item, DiffItem::Same(_))));
+        assert!(OrderedSet::<i32>::default().diff(&OrderedSet::new()).is_empty());
+    }
+}
@@ -0,0 +1,697 @@
+//! A small map backed by a vector of key-value pairs.
+//!
+//! This module provides the `VecMap` struct, a map that only requires its keys to
+//! implement `PartialEq`, which makes it usable with keys that can't be hashed or ordered.
+
+/// A map backed by a vector of key-value pairs.
+///
+/// `VecMap` performs a linear scan for every lookup, so it is only meant for small
+/// collections, where it is often faster than a `HashMap` anyway. In exchange, keys
+/// only need to implement `PartialEq`, and entries are kept in insertion order.
+///
+/// # Type Parameters
+///
+/// * `K` - The type of the keys.
+/// * `V` - The type of the values.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::collections::vec_map::VecMap;
+///
+/// let mut map = VecMap::new();
+/// map.insert("one", 1);
+/// map.insert("two", 2);
+///
+/// assert_eq!(map.get(&"one"), Some(&1));
+/// assert!(map.contains_key(&"two"));
+/// assert_eq!(map.remove(&"one"), Some(1));
+/// assert!(!map.contains_key(&"one"));
+/// ```
+#[derive(Debug)]
+pub struct VecMap<K, V> {
+    /// The entries of the map, in insertion order
+    entries: Vec<(K, V)>,
+}
+
+impl<K, V> VecMap<K, V> {
+    /// Creates a new, empty `VecMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::vec_map::VecMap;
+    ///
+    /// let map: VecMap<&str, i32> = VecMap::new();
+    /// assert_eq!(map.iter().count(), 0);
+    /// ```
+    pub fn new() -> Self {
+        Self { entries: Vec::new() }
+    }
+
+    /// Returns an iterator over the entries of the map, in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::vec_map::VecMap;
+    ///
+    /// let mut map = VecMap::new();
+    /// map.insert("one", 1);
+    /// map.insert("two", 2);
+    ///
+    /// let entries: Vec<_> = map.iter().collect();
+    /// assert_eq!(entries, [&("one", 1), &("two", 2)]);
+    /// ```
+    pub fn iter(&self) -> std::slice::Iter<'_, (K, V)> {
+        self.entries.iter()
+    }
+
+    /// Returns an iterator over the keys of the map, in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::vec_map::VecMap;
+    ///
+    /// let map = VecMap::from(vec![("one", 1), ("two", 2)]);
+    /// assert_eq!(map.keys().collect::<Vec<_>>(), [&"one", &"two"]);
+    /// ```
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values of the map, in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::vec_map::VecMap;
+    ///
+    /// let map = VecMap::from(vec![("one", 1), ("two", 2)]);
+    /// assert_eq!(map.values().sum::<i32>(), 3);
+    /// ```
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    /// Returns an iterator over mutable references to the values of the map, in
+    /// insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::vec_map::VecMap;
+    ///
+    /// let mut map = VecMap::from(vec![("one", 1), ("two", 2)]);
+    /// for value in map.values_mut() {
+    ///     *value *= 10;
+    /// }
+    /// assert_eq!(map.get(&"two"), Some(&20));
+    /// ```
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes and returns the most recently inserted entry.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((K, V))` - The last entry of the map.
+    /// * `None` - If the map is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::vec_map::VecMap;
+    ///
+    /// let mut map = VecMap::new();
+    /// map.insert("one", 1);
+    /// map.insert("two", 2);
+    ///
+    /// assert_eq!(map.pop(), Some(("two", 2)));
+    /// assert_eq!(map.pop(), Some(("one", 1)));
+    /// assert_eq!(map.pop(), None);
+    /// ```
+    pub fn pop(&mut self) -> Option<(K, V)> {
+        self.entries.pop()
+    }
+
+    /// Returns the most recently inserted entry without removing it.
+    ///
+    /// # Returns
+    ///
+    /// * `Some((&K, &V))` - The last entry of the map.
+    /// * `None` - If the map is empty.
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.entries.last().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K, V> VecMap<K, V>
+where
+    K: PartialEq,
+{
+    /// Returns a reference to the value associated with `key`, if any.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - The key to look up.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(&V)` - The value associated with `key`.
+    /// * `None` - If the map doesn't contain `key`.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Returns a mutable reference to the value associated with `key`, if any.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - The key to look up.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(&mut V)` - The value associated with `key`, to modify in place.
+    /// * `None` - If the map doesn't contain `key`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::vec_map::VecMap;
+    ///
+    /// let mut map = VecMap::from(vec![("plays", 1)]);
+    /// if let Some(plays) = map.get_mut(&"plays") {
+    ///     *plays += 1;
+    /// }
+    /// assert_eq!(map.get(&"plays"), Some(&2));
+    /// ```
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.entries.iter_mut()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Inserts a key-value pair into the map.
+    ///
+    /// If the map already contains `key`, its value is replaced in place, keeping the
+    /// entry's position in the insertion order.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - The key to insert.
+    /// * `value` - The value to associate with `key`.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(V)` - The value previously associated with `key`.
+    /// * `None` - If the map didn't contain `key`.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.position(&key) {
+            Some(index) => Some(std::mem::replace(&mut self.entries[index].1, value)),
+            None => {
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Gets the entry for `key`, to read or insert its value with a single lookup.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - The key of the entry.
+    ///
+    /// # Returns
+    ///
+    /// * `VecMapEntry::Occupied` - If the map contains `key`.
+    /// * `VecMapEntry::Vacant` - Otherwise. Inserting through it appends the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::collections::vec_map::VecMap;
+    ///
+    /// let mut counts = VecMap::new();
+    /// for word in ["a", "b", "a"] {
+    ///     *counts.entry(word).or_insert(0) += 1;
+    /// }
+    /// assert_eq!(counts.get(&"a"), Some(&2));
+    /// assert_eq!(counts.get(&"b"), Some(&1));
+    /// ```
+    pub fn entry(&mut self, key: K) -> VecMapEntry<'_, K, V> {
+        let index = self.position(&key);
+        let entries = &mut self.entries;
+        match index {
+            Some(index) => VecMapEntry::Occupied(OccupiedEntry { entries, index }),
+            None => VecMapEntry::Vacant(VacantEntry { entries, key }),
+        }
+    }
+
+    /// Removes `key` from the map, preserving the order of the remaining entries.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - The key to remove.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(V)` - The value that was associated with `key`.
+    /// * `None` - If the map didn't contain `key`.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.position(key).map(|index| self.entries.remove(index).1)
+    }
+
+    /// Checks whether the map contains `key`.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Returns the index of the entry for `key`, if any.
+    fn position(&self, key: &K) -> Option<usize> {
+        self.entries.iter().position(|(k, _)| k == key)
+    }
+}
+
+/// A view into a single entry of a [`VecMap`], returned by [`VecMap::entry`].
+#[derive(Debug)]
+pub enum VecMapEntry<'a, K, V> {
+    /// An entry whose key is in the map.
+    Occupied(OccupiedEntry<'a, K, V>),
+    /// An entry whose key isn't in the map.
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+impl<'a, K, V> VecMapEntry<'a, K, V> {
+    /// Returns the value of the entry, inserting `default` first if it's vacant.
+    ///
+    /// # Parameters
+    ///
+    /// * `default` - The value to insert if the entry is vacant.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the value of the entry.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Returns the value of the entry, inserting the result of `f` first if it's vacant.
+    ///
+    /// # Parameters
+    ///
+    /// * `f` - The function computing the value to insert, only called if the entry
+    ///   is vacant.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the value of the entry.
+    pub fn or_insert_with<F>(self, f: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            VecMapEntry::Occupied(entry) => entry.into_mut(),
+            VecMapEntry::Vacant(entry) => entry.insert(f()),
+        }
+    }
+
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        match self {
+            VecMapEntry::Occupied(entry) => entry.key(),
+            VecMapEntry::Vacant(entry) => entry.key(),
+        }
+    }
+}
+
+/// An entry of a [`VecMap`] whose key is in the map.
+#[derive(Debug)]
+pub struct OccupiedEntry<'a, K, V> {
+    /// The entries of the map
+    entries: &'a mut Vec<(K, V)>,
+    /// The index of this entry in `entries`
+    index: usize,
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        &self.entries[self.index].0
+    }
+
+    /// Returns a reference to the value of the entry.
+    pub fn get(&self) -> &V {
+        &self.entries[self.index].1
+    }
+
+    /// Returns a mutable reference to the value of the entry.
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.entries[self.index].1
+    }
+
+    /// Converts the entry into a mutable reference to its value, bound to the map.
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.entries[self.index].1
+    }
+
+    /// Replaces the value of the entry, returning the previous one.
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+}
+
+/// An entry of a [`VecMap`] whose key isn't in the map.
+#[derive(Debug)]
+pub struct VacantEntry<'a, K, V> {
+    /// The entries of the map
+    entries: &'a mut Vec<(K, V)>,
+    /// The key of the entry
+    key: K,
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Appends the entry to the map with the given value.
+    ///
+    /// # Returns
+    ///
+    /// A mutable reference to the inserted value.
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.entries.push((self.key, value));
+        let (_, value) = self.entries.last_mut().expect("An entry was just pushed");
+        value
+    }
+}
+
+impl<K, V> Default for VecMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Clone for VecMap<K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    fn clone(&self) -> Self {
+        Self { entries: self.entries.clone() }
+    }
+}
+
+impl<K, V> PartialEq for VecMap<K, V>
+where
+    K: PartialEq,
+    V: PartialEq,
+{
+    /// Checks whether both maps contain the same key-value pairs, in any order.
+    ///
+    /// Since keys are unique, this is a linear scan of the other map for every entry.
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self.entries.iter().all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K, V> Eq for VecMap<K, V>
+where
+    K: Eq,
+    V: Eq,
+{
+}
+
+impl<K, V> From<Vec<(K, V)>> for VecMap<K, V>
+where
+    K: PartialEq,
+{
+    /// Creates a map from a vector of pairs.
+    ///
+    /// When a key appears several times, the last value wins, as with repeated `insert`s.
+    fn from(pairs: Vec<(K, V)>) -> Self {
+        let mut map = Self { entries: Vec::with_capacity(pairs.len()) };
+        for (key, value) in pairs {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<K, V> From<VecMap<K, V>> for Vec<(K, V)> {
+    /// Consumes the map into its pairs, in insertion order.
+    fn from(map: VecMap<K, V>) -> Self {
+        map.entries
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for VecMap<K, V>
+where
+    K: PartialEq,
+{
+    /// Creates a map from an iterator of pairs.
+    ///
+    /// When a key appears several times, the last value wins, as with repeated `insert`s.
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<K, V> IntoIterator for VecMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = std::vec::IntoIter<(K, V)>;
+
+    /// Consumes the map into an iterator over its pairs, in insertion order.
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a VecMap<K, V> {
+    type Item = &'a (K, V);
+    type IntoIter = std::slice::Iter<'a, (K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut map = VecMap::new();
+        assert_eq!(map.insert(1, "a"), None);
+        assert_eq!(map.insert(2, "b"), None);
+
+        assert_eq!(map.get(&1), Some(&"a"));
+        assert_eq!(map.get(&2), Some(&"b"));
+        assert_eq!(map.get(&3), None);
+    }
+
+    #[test]
+    fn test_insert_replaces_in_place() {
+        let mut map = VecMap::new();
+        map.insert(1, "a");
+        map.insert(2, "b");
+        assert_eq!(map.insert(1, "c"), Some("a"));
+
+        let entries: Vec<_> = map.iter().collect();
+        assert_eq!(entries, [&(1, "c"), &(2, "b")]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut map = VecMap::new();
+        map.insert(1, "a");
+        map.insert(2, "b");
+        map.insert(3, "c");
+
+        assert_eq!(map.remove(&2), Some("b"));
+        assert_eq!(map.remove(&2), None);
+        assert!(!map.contains_key(&2));
+
+        let entries: Vec<_> = map.iter().collect();
+        assert_eq!(entries, [&(1, "a"), &(3, "c")]);
+    }
+
+    #[test]
+    fn test_non_hashable_keys() {
+        let mut map = VecMap::new();
+        map.insert(1.5, "a");
+        map.insert(2.5, "b");
+        assert_eq!(map.get(&2.5), Some(&"b"));
+    }
+
+    #[test]
+    fn test_from_vec() {
+        let map = VecMap::from(vec![(1, "a"), (2, "b"), (1, "c")]);
+
+        assert_eq!(map.get(&1), Some(&"c"));
+        assert_eq!(map.get(&2), Some(&"b"));
+        assert_eq!(map.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_into_vec() {
+        let mut map = VecMap::new();
+        map.insert(2, "b");
+        map.insert(1, "a");
+
+        let pairs: Vec<(i32, &str)> = map.into();
+        assert_eq!(pairs, [(2, "b"), (1, "a")]);
+    }
+
+    #[test]
+    fn test_pop_and_last() {
+        let mut map = VecMap::new();
+        assert_eq!(map.last(), None);
+        assert_eq!(map.pop(), None);
+
+        map.insert(1, "a");
+        map.insert(2, "b");
+        map.insert(3, "c");
+        assert_eq!(map.last(), Some((&3, &"c")));
+
+        assert_eq!(map.pop(), Some((3, "c")));
+        assert_eq!(map.last(), Some((&2, &"b")));
+
+        let entries: Vec<_> = map.iter().collect();
+        assert_eq!(entries, [&(1, "a"), &(2, "b")]);
+    }
+
+    #[test]
+    fn test_from_iter_last_wins() {
+        let map: VecMap<_, _> = [(1, "a"), (1, "b")].into_iter().collect();
+
+        assert_eq!(map.get(&1), Some(&"b"));
+        assert_eq!(map.iter().count(), 1);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let map: VecMap<_, _> = [(2, "b"), (1, "a"), (3, "c")].into_iter().collect();
+
+        let borrowed: Vec<_> = (&map).into_iter().collect();
+        assert_eq!(borrowed, [&(2, "b"), &(1, "a"), &(3, "c")]);
+
+        let mut keys = Vec::new();
+        for (key, _) in &map {
+            keys.push(*key);
+        }
+        assert_eq!(keys, [2, 1, 3]);
+
+        let owned: Vec<_> = map.into_iter().collect();
+        assert_eq!(owned, [(2, "b"), (1, "a"), (3, "c")]);
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut map = VecMap::from(vec![(1, String::from("a")), (2, String::from("b"))]);
+
+        map.get_mut(&2).unwrap().push('c');
+        assert!(map.get_mut(&3).is_none());
+
+        assert_eq!(map.get(&2).map(String::as_str), Some("bc"));
+        assert_eq!(map.get(&1).map(String::as_str), Some("a"));
+    }
+
+    #[test]
+    fn test_keys_values_and_len() {
+        let mut map = VecMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.len(), 0);
+        assert_eq!(map.keys().count(), 0);
+
+        map.insert(3, 30);
+        map.insert(1, 10);
+        map.insert(2, 20);
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), [3, 1, 2]);
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), [30, 10, 20]);
+
+        map.values_mut().for_each(|value| *value += 1);
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), [31, 11, 21]);
+        assert_eq!(map.len(), 3);
+        assert!(!map.is_empty());
+    }
+
+    #[test]
+    fn test_entry_word_count() {
+        let mut counts = VecMap::new();
+        for word in "the cat saw the other cat near the door".split(' ') {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+
+        let entries: Vec<_> = counts.iter().collect();
+        assert_eq!(entries, [
+            &("the", 3), &("cat", 2), &("saw", 1), &("other", 1), &("near", 1), &("door", 1),
+        ]);
+    }
+
+    #[test]
+    fn test_entry_variants() {
+        let mut map = VecMap::from(vec![(1, "a")]);
+
+        match map.entry(1) {
+            VecMapEntry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), &1);
+                assert_eq!(entry.insert("b"), "a");
+                assert_eq!(entry.get(), &"b");
+            }
+            VecMapEntry::Vacant(_) => panic!("1 is in the map"),
+        }
+
+        let entry = map.entry(2);
+        assert!(matches!(entry, VecMapEntry::Vacant(_)));
+        assert_eq!(entry.key(), &2);
+
+        // The default is only computed for vacant entries
+        assert_eq!(*map.entry(1).or_insert_with(|| panic!("1 is in the map")), "b");
+        assert_eq!(*map.entry(2).or_insert_with(|| "c"), "c");
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_eq_ignores_order() {
+        let map = VecMap::from(vec![(1, "a"), (2, "b")]);
+        assert_eq!(map, VecMap::from(vec![(2, "b"), (1, "a")]));
+
+        assert_ne!(map, VecMap::from(vec![(1, "a"), (2, "c")]));
+        assert_ne!(map, VecMap::from(vec![(1, "a")]));
+        assert_ne!(map, VecMap::from(vec![(1, "a"), (2, "b"), (3, "c")]));
+        assert_eq!(VecMap::<i32, i32>::new(), VecMap::new());
+    }
+
+    #[test]
+    fn test_clone() {
+        let mut map = VecMap::from(vec![(1, String::from("a"))]);
+        let snapshot = map.clone();
+
+        map.get_mut(&1).unwrap().push('b');
+        assert_eq!(snapshot.get(&1).map(String::as_str), Some("a"));
+        assert_ne!(map, snapshot);
+    }
+}
@@ -3,9 +3,18 @@
 //! This module provides utility functions for common I/O operations,
 //! such as creating directory structures for files.
 
-use std::fs::create_dir_all;
-use std::io;
-use std::path::PathBuf;
+use std::collections::hash_map::RandomState;
+use std::ffi::OsString;
+use std::fs::{self, create_dir_all, File};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+/// A process-wide counter used to generate unique temporary names.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 /// Creates all parent directories for a given path.
 ///
@@ -58,6 +67,357 @@ pub fn create_dir_all_for(path: PathBuf) -> io::Result<()> {
     Ok(())
 }
 
+/// Replaces a directory with freshly built contents.
+///
+/// A temporary sibling directory is created next to `target`, and `build` is called
+/// to populate it. Once `build` succeeds, the old `target` (if any) is moved aside,
+/// the temporary directory is renamed to `target`, and the old contents are removed.
+/// If `build` fails, the temporary directory is removed and `target` is left untouched.
+///
+/// # Parameters
+///
+/// * `target` - The directory to replace.
+/// * `build` - A function populating the directory it is given.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Ok if the directory was replaced, or the first error encountered.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::io::atomic_replace_dir;
+/// use std::fs;
+///
+/// let target = std::env::temp_dir().join("cutoff_example_replace_dir");
+/// let _ = fs::remove_dir_all(&target);
+///
+/// atomic_replace_dir(&target, |dir| fs::write(dir.join("output.txt"), "hello")).unwrap();
+/// assert_eq!(fs::read_to_string(target.join("output.txt")).unwrap(), "hello");
+///
+/// let _ = fs::remove_dir_all(&target);
+/// ```
+///
+/// # Note
+///
+/// Renames are only atomic within a single filesystem, which is why the temporary
+/// directory is created beside `target` rather than in the system temp directory.
+/// The parent of `target` must therefore exist and be writable. Since a directory
+/// cannot be renamed over a non-empty one, the swap happens in two renames, leaving
+/// a brief window where `target` does not exist.
+pub fn atomic_replace_dir<F>(target: impl AsRef<Path>, build: F) -> io::Result<()>
+where
+    F: FnOnce(&Path) -> io::Result<()>,
+{
+    let target = target.as_ref();
+    let temp = sibling_path(target, "tmp")?;
+
+    fs::create_dir(&temp)?;
+    if let Err(err) = build(&temp) {
+        let _ = fs::remove_dir_all(&temp);
+        return Err(err);
+    }
+
+    if !target.exists() {
+        return fs::rename(&temp, target).inspect_err(|_| {
+            let _ = fs::remove_dir_all(&temp);
+        });
+    }
+
+    let old = sibling_path(target, "old")?;
+    if let Err(err) = fs::rename(target, &old) {
+        let _ = fs::remove_dir_all(&temp);
+        return Err(err);
+    }
+    if let Err(err) = fs::rename(&temp, target) {
+        // Put the previous contents back before giving up
+        let _ = fs::rename(&old, target);
+        let _ = fs::remove_dir_all(&temp);
+        return Err(err);
+    }
+    fs::remove_dir_all(&old)
+}
+
+/// Checks whether two paths refer to the same file.
+///
+/// Both paths are canonicalized, resolving symbolic links and relative components,
+/// before being compared. This catches cases such as copying a file onto itself
+/// through a different path.
+///
+/// # Parameters
+///
+/// * `a` - The first path.
+/// * `b` - The second path.
+///
+/// # Returns
+///
+/// * `io::Result<bool>` - Whether both paths resolve to the same file, or an error
+///   if either path doesn't exist or can't be resolved.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::io::same_file;
+///
+/// let dir = std::env::temp_dir();
+/// assert!(same_file(&dir, dir.join(".")).unwrap());
+/// ```
+pub fn same_file(a: impl AsRef<Path>, b: impl AsRef<Path>) -> io::Result<bool> {
+    Ok(fs::canonicalize(a)? == fs::canonicalize(b)?)
+}
+
+/// Checks that `path` can be written to, to fail fast before a long write.
+///
+/// The check is done by actually attempting a write, which is the only reliable way
+/// to account for permissions, ACLs, read-only mounts and the privileges of the process:
+///
+/// * An existing file is opened for appending, without changing its content.
+/// * An existing directory gets a temporary probe file created and removed in it.
+/// * A path that doesn't exist yet is checked through its parent directory.
+///
+/// # Parameters
+///
+/// * `path` - The file or directory to check.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Ok if `path` is writable, a `PermissionDenied` error naming the
+///   path if it isn't, or the underlying error if the check couldn't be made (for
+///   instance if the parent directory doesn't exist).
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::io::ensure_writable;
+///
+/// let dir = std::env::temp_dir();
+/// assert!(ensure_writable(&dir).is_ok());
+/// assert!(ensure_writable(dir.join("not-created-yet.txt")).is_ok());
+/// ```
+pub fn ensure_writable(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref();
+    let result = match fs::metadata(path) {
+        Ok(metadata) if metadata.is_dir() => probe_dir(path),
+        Ok(_) => fs::OpenOptions::new().append(true).open(path).map(|_| ()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            let parent = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            probe_dir(parent)
+        }
+        Err(err) => Err(err),
+    };
+
+    result.map_err(|err| match err.kind() {
+        io::ErrorKind::PermissionDenied => io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is not writable: {}", path.display(), err),
+        ),
+        _ => err,
+    })
+}
+
+/// Reads a frame of exactly `len` bytes from a reader.
+///
+/// # Parameters
+///
+/// * `reader` - The reader to read the frame from.
+/// * `len` - The size of the frame, in bytes.
+///
+/// # Returns
+///
+/// * `io::Result<Vec<u8>>` - The frame, or an `UnexpectedEof` error telling how many
+///   bytes were read if the reader ends before the frame is complete.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::io::read_frame;
+///
+/// let mut reader: &[u8] = b"headerbody";
+/// assert_eq!(read_frame(&mut reader, 6).unwrap(), b"header");
+/// assert_eq!(read_frame(&mut reader, 4).unwrap(), b"body");
+/// assert!(read_frame(&mut reader, 1).is_err());
+/// ```
+pub fn read_frame<R: Read>(reader: &mut R, len: usize) -> io::Result<Vec<u8>> {
+    let mut frame = vec![0; len];
+    read_frame_into(reader, &mut frame)?;
+    Ok(frame)
+}
+
+/// Reads a frame filling exactly `buf` from a reader, reusing the caller's buffer.
+///
+/// This is the allocation-free counterpart of [`read_frame`].
+///
+/// # Parameters
+///
+/// * `reader` - The reader to read the frame from.
+/// * `buf` - The buffer to fill. Its length is the size of the frame.
+///
+/// # Returns
+///
+/// * `io::Result<()>` - Ok once `buf` is filled, or an `UnexpectedEof` error telling
+///   how many bytes were read if the reader ends before the frame is complete. The
+///   content of `buf` is unspecified on error.
+pub fn read_frame_into<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<()> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("short frame: expected {} bytes, got {}", buf.len(), filled),
+                ));
+            }
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Reads a whole file into memory, failing if it is larger than `max` bytes.
+///
+/// This is the safe way to read an untrusted file, which could otherwise exhaust the
+/// memory. The size is checked on the opened file's metadata first, to fail fast without
+/// reading anything. Since the file may still grow after that check, the read itself is
+/// bounded to `max` bytes too.
+///
+/// # Parameters
+///
+/// * `path` - The path of the file to read.
+/// * `max` - The maximum size of the file, in bytes.
+///
+/// # Returns
+///
+/// * `io::Result<Vec<u8>>` - The content of the file, or a `FileTooLarge` error if it
+///   is larger than `max` bytes.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::io::read_to_bytes_limited;
+/// use std::io;
+///
+/// let path = std::env::temp_dir().join("read_to_bytes_limited_example.txt");
+/// std::fs::write(&path, b"content").unwrap();
+///
+/// assert_eq!(read_to_bytes_limited(&path, 1024).unwrap(), b"content");
+/// assert_eq!(read_to_bytes_limited(&path, 4).unwrap_err().kind(), io::ErrorKind::FileTooLarge);
+/// # std::fs::remove_file(path).unwrap();
+/// ```
+pub fn read_to_bytes_limited(path: impl AsRef<Path>, max: usize) -> io::Result<Vec<u8>> {
+    let path = path.as_ref();
+    let too_large = || io::Error::new(
+        io::ErrorKind::FileTooLarge,
+        format!("{} is larger than the limit of {} bytes", path.display(), max),
+    );
+
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len > max as u64 {
+        return Err(too_large());
+    }
+
+    // One extra byte tells whether the file grew past the limit since the check
+    let mut content = Vec::with_capacity(len as usize);
+    file.take(max as u64 + 1).read_to_end(&mut content)?;
+    if content.len() > max {
+        return Err(too_large());
+    }
+    Ok(content)
+}
+
+/// Creates a new, uniquely named temporary file in `dir`.
+///
+/// This is the first step of an atomic write: write into the temporary file, then rename
+/// it over the destination. Creating it in the destination's directory keeps both on the
+/// same filesystem, which the rename requires.
+///
+/// The file name is `<prefix><pid>-<counter>-<random>.tmp`, and the file is created
+/// exclusively, so it never clobbers an existing file, even one created concurrently by
+/// another process. The file isn't removed automatically.
+///
+/// # Parameters
+///
+/// * `dir` - The directory to create the file in.
+/// * `prefix` - The beginning of the file name, e.g. `".export-"`.
+///
+/// # Returns
+///
+/// * `io::Result<(File, PathBuf)>` - The file, opened for writing, and its path.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::io::temp_file_in;
+/// use std::io::Write;
+///
+/// let (mut file, path) = temp_file_in(std::env::temp_dir(), "example-").unwrap();
+/// file.write_all(b"content").unwrap();
+/// assert!(path.file_name().unwrap().to_str().unwrap().starts_with("example-"));
+/// # std::fs::remove_file(path).unwrap();
+/// ```
+pub fn temp_file_in(dir: impl AsRef<Path>, prefix: &str) -> io::Result<(File, PathBuf)> {
+    // A few attempts, in case a random suffix collides with a leftover file
+    const ATTEMPTS: usize = 8;
+
+    let dir = dir.as_ref();
+    let mut last_error = None;
+    for _ in 0..ATTEMPTS {
+        let name = format!(
+            "{}{}-{}-{:016x}.tmp",
+            prefix,
+            process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed),
+            random_u64()
+        );
+        let path = dir.join(name);
+        match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((file, path)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => last_error = Some(err),
+            Err(err) => return Err(err),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| io::Error::other("could not create a temporary file")))
+}
+
+/// Returns a random number, good enough to make names unpredictable but not for
+/// cryptography. It comes from the randomly seeded hasher of the standard library.
+fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    if let Ok(elapsed) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(elapsed.as_nanos());
+    }
+    hasher.finish()
+}
+
+/// Checks that files can be created in `dir` by creating and removing a probe file.
+fn probe_dir(dir: &Path) -> io::Result<()> {
+    let probe = sibling_path(&dir.join("writable"), "probe")?;
+    fs::OpenOptions::new().write(true).create_new(true).open(&probe)?;
+    fs::remove_file(&probe)
+}
+
+/// Builds a unique, hidden path next to `path`, tagged with the given `kind`.
+fn sibling_path(path: &Path, kind: &str) -> io::Result<PathBuf> {
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "path has no file name")
+    })?;
+
+    let mut sibling = OsString::from(".");
+    sibling.push(name);
+    sibling.push(format!(
+        ".{}-{}-{}",
+        kind,
+        process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    Ok(path.with_file_name(sibling))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,4 +468,204 @@ mod tests {
         // Clean up
         let _ = fs::remove_dir_all(&temp_dir);
     }
+
+    #[test]
+    fn test_atomic_replace_dir() {
+        let target = std::env::temp_dir().join("cutoff_common_test_replace_dir");
+        let _ = fs::remove_dir_all(&target);
+
+        // Replace a directory that does not exist yet
+        atomic_replace_dir(&target, |dir| fs::write(dir.join("a.txt"), "first")).unwrap();
+        assert_eq!(fs::read_to_string(target.join("a.txt")).unwrap(), "first");
+
+        // Replace existing contents, old files must be gone
+        atomic_replace_dir(&target, |dir| fs::write(dir.join("b.txt"), "second")).unwrap();
+        assert!(!target.join("a.txt").exists());
+        assert_eq!(fs::read_to_string(target.join("b.txt")).unwrap(), "second");
+
+        // A failing build leaves the target untouched
+        let result = atomic_replace_dir(&target, |_| Err(io::Error::other("build failed")));
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(target.join("b.txt")).unwrap(), "second");
+
+        // No temporary siblings are left behind
+        let leftovers = fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                entry.file_name().to_string_lossy().starts_with(".cutoff_common_test_replace_dir")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        let _ = fs::remove_dir_all(&target);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_same_file() {
+        let temp_dir = std::env::temp_dir().join("cutoff_common_test_same_file");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let file = temp_dir.join("file.txt");
+        let other = temp_dir.join("other.txt");
+        let link = temp_dir.join("link.txt");
+        fs::write(&file, "content").unwrap();
+        fs::write(&other, "content").unwrap();
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+
+        assert!(same_file(&file, &link).unwrap());
+        assert!(same_file(&file, temp_dir.join("./file.txt")).unwrap());
+        assert!(!same_file(&file, &other).unwrap());
+
+        // A missing path is an error rather than a mismatch
+        assert!(same_file(&file, temp_dir.join("missing.txt")).is_err());
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_read_frame_exact() {
+        let mut reader: &[u8] = b"abcd";
+        assert_eq!(read_frame(&mut reader, 4).unwrap(), b"abcd");
+        assert!(reader.is_empty());
+
+        assert_eq!(read_frame(&mut reader, 0).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_read_frame_short() {
+        let mut reader: &[u8] = b"abc";
+        let err = read_frame(&mut reader, 4).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(err.to_string().contains("expected 4 bytes, got 3"));
+    }
+
+    #[test]
+    fn test_read_frame_over_long() {
+        let mut reader: &[u8] = b"abcdef";
+        assert_eq!(read_frame(&mut reader, 4).unwrap(), b"abcd");
+        // The rest of the data is left for the next frame
+        assert_eq!(reader, b"ef");
+    }
+
+    #[test]
+    fn test_read_frame_into() {
+        let mut reader: &[u8] = b"aabbc";
+        let mut buf = [0; 2];
+
+        read_frame_into(&mut reader, &mut buf).unwrap();
+        assert_eq!(&buf, b"aa");
+        read_frame_into(&mut reader, &mut buf).unwrap();
+        assert_eq!(&buf, b"bb");
+
+        let err = read_frame_into(&mut reader, &mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_ensure_writable() {
+        let temp_dir = std::env::temp_dir().join("cutoff_common_test_ensure_writable");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let file = temp_dir.join("file.txt");
+        fs::write(&file, "content").unwrap();
+
+        assert!(ensure_writable(&temp_dir).is_ok());
+        assert!(ensure_writable(&file).is_ok());
+        assert!(ensure_writable(temp_dir.join("new.txt")).is_ok());
+        // Neither the file's content nor the directory's is touched
+        assert_eq!(fs::read_to_string(&file).unwrap(), "content");
+        assert_eq!(fs::read_dir(&temp_dir).unwrap().count(), 1);
+
+        let missing_parent = temp_dir.join("missing").join("new.txt");
+        assert_eq!(ensure_writable(missing_parent).unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_ensure_writable_read_only_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = std::env::temp_dir().join("cutoff_common_test_ensure_writable_ro");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+        fs::set_permissions(&temp_dir, fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Privileged users (e.g. root in a container) can write anyway
+        let privileged = fs::write(temp_dir.join("privileged"), "").is_ok();
+        if !privileged {
+            let err = ensure_writable(&temp_dir).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+            assert!(err.to_string().contains("is not writable"));
+
+            let err = ensure_writable(temp_dir.join("new.txt")).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        }
+
+        fs::set_permissions(&temp_dir, fs::Permissions::from_mode(0o755)).unwrap();
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_temp_file_in() {
+        use std::io::Write;
+
+        let temp_dir = std::env::temp_dir().join("cutoff_common_test_temp_file_in");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let (mut first, first_path) = temp_file_in(&temp_dir, ".export-").unwrap();
+        let (_, second_path) = temp_file_in(&temp_dir, ".export-").unwrap();
+
+        assert_ne!(first_path, second_path);
+        for path in [&first_path, &second_path] {
+            assert_eq!(path.parent(), Some(temp_dir.as_path()));
+            let name = path.file_name().unwrap().to_str().unwrap();
+            assert!(name.starts_with(&format!(".export-{}-", process::id())));
+            assert!(name.ends_with(".tmp"));
+        }
+
+        // The file is ready to be written, then renamed into place
+        first.write_all(b"content").unwrap();
+        let target = temp_dir.join("export.txt");
+        fs::rename(&first_path, &target).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "content");
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_read_to_bytes_limited() {
+        let temp_dir = std::env::temp_dir().join("cutoff_common_test_read_to_bytes_limited");
+        let _ = fs::remove_dir_all(&temp_dir);
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        let file = temp_dir.join("data.bin");
+        fs::write(&file, [7u8; 100]).unwrap();
+
+        // Under and exactly at the limit
+        assert_eq!(read_to_bytes_limited(&file, 1000).unwrap(), [7u8; 100]);
+        assert_eq!(read_to_bytes_limited(&file, 100).unwrap().len(), 100);
+
+        // Over the limit
+        let err = read_to_bytes_limited(&file, 99).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::FileTooLarge);
+        assert!(err.to_string().contains("larger than the limit of 99 bytes"));
+
+        let empty = temp_dir.join("empty.bin");
+        fs::write(&empty, b"").unwrap();
+        assert!(read_to_bytes_limited(&empty, 0).unwrap().is_empty());
+
+        assert_eq!(
+            read_to_bytes_limited(temp_dir.join("missing.bin"), 10).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
 }
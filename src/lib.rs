@@ -14,6 +14,9 @@
 //! - URN handling
 //! - Optional logging utilities (with the `tracing-subscriber` feature)
 //! - Optional serialization support (with the `serde` feature)
+//! - Optional compact binary encoding of URNs (with the `binary` feature)
+//! - Optional random sampling within ranges (with the `rand` feature)
+//! - Optional test helpers, such as the URN round-trip check (with the `testutil` feature)
 
 pub mod urn;
 pub mod collections;
@@ -22,9 +25,19 @@ pub mod io;
 #[cfg(feature = "tracing-subscriber")]
 pub mod logging;
 
+#[cfg(test)]
+mod test_support;
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
 use std::thread;
 use std::thread::JoinHandle;
 
+/// The global counter used by `thread_spawn_numbered` to name threads.
+static THREAD_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 /// A trait for converting a value into a `Result::Ok` variant.
 ///
 /// This trait provides a convenient way to wrap any value in a `Result::Ok`,
@@ -157,6 +170,260 @@ where
     thread::Builder::new().name(name.into()).spawn(f).unwrap()
 }
 
+/// Creates a new thread named after `base` and a global counter, and executes the provided function.
+///
+/// Each call appends the next value of a process-wide counter to `base`, so threads
+/// spawned with the same base name stay distinguishable in debuggers and profilers:
+/// `"worker-1"`, `"worker-2"`, and so on. The counter is shared across all base names.
+///
+/// # Parameters
+///
+/// * `base` - The base name of the thread, to which `-<n>` is appended.
+/// * `f` - The function to execute in the new thread.
+///
+/// # Returns
+///
+/// A `JoinHandle` that can be used to wait for the thread to complete and
+/// retrieve its result.
+///
+/// # Panics
+///
+/// This function will panic if thread creation fails.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::thread_spawn_numbered;
+///
+/// let handle = thread_spawn_numbered("worker", || 42);
+/// assert!(handle.thread().name().unwrap().starts_with("worker-"));
+/// assert_eq!(handle.join().unwrap(), 42);
+/// ```
+pub fn thread_spawn_numbered<F, T>(base: &str, f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T,
+    F: Send + 'static,
+    T: Send + 'static,
+{
+    let number = THREAD_COUNTER.fetch_add(1, Ordering::Relaxed) + 1;
+    thread_spawn(&format!("{}-{}", base, number), f)
+}
+
+/// Creates a new thread with the specified name, running the provided function within the current tracing span.
+///
+/// Spawned threads don't inherit the tracing context of their parent, so their events
+/// would not be correlated with the span that started them. This captures the current
+/// span, along with the current subscriber, and enters them in the new thread for the
+/// duration of `f`.
+///
+/// # Parameters
+///
+/// * `name` - The name to assign to the thread.
+/// * `f` - The function to execute in the new thread.
+///
+/// # Returns
+///
+/// A `JoinHandle` that can be used to wait for the thread to complete and
+/// retrieve its result.
+///
+/// # Panics
+///
+/// This function will panic if thread creation fails.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::thread_spawn_with_current_span;
+///
+/// let span = tracing::info_span!("request", id = 42);
+/// let _entered = span.enter();
+///
+/// let handle = thread_spawn_with_current_span("worker", || {
+///     tracing::info!("this event is recorded within the request span");
+///     42
+/// });
+/// assert_eq!(handle.join().unwrap(), 42);
+/// ```
+pub fn thread_spawn_with_current_span<F, T>(name: &str, f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T,
+    F: Send + 'static,
+    T: Send + 'static,
+{
+    let span = tracing::Span::current();
+    let dispatch = tracing::dispatcher::get_default(|dispatch| dispatch.clone());
+    thread_spawn(name, move || {
+        tracing::dispatcher::with_default(&dispatch, || span.in_scope(f))
+    })
+}
+
+/// Resets the counter used by `thread_spawn_numbered`, so the next thread is numbered 1.
+///
+/// This is mostly useful in tests that assert on thread names.
+pub fn reset_thread_counter() {
+    THREAD_COUNTER.store(0, Ordering::Relaxed);
+}
+
+/// A panic caught in a worker of a [`WorkerGroup`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerPanic {
+    /// The name of the worker thread that panicked
+    pub name: String,
+    /// The panic message, or a placeholder if the payload isn't a string
+    pub message: String,
+}
+
+/// A group of named worker threads whose panics are collected centrally.
+///
+/// Each worker runs within `catch_unwind`, so a panic doesn't surface as a separate
+/// `Err` when joining its thread: it is sent as a [`WorkerPanic`] to a channel shared
+/// by the group, which can be watched with [`WorkerGroup::panics`] while the workers
+/// run, and is drained by [`WorkerGroup::join_all`]. The panic hook still runs as
+/// usual, so panics are printed to stderr by default.
+///
+/// # Type Parameters
+///
+/// * `T` - The type returned by the workers.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::WorkerGroup;
+///
+/// let mut group = WorkerGroup::new();
+/// group.spawn("worker-1", || 1);
+/// group.spawn("worker-2", || -> i32 { panic!("out of work") });
+/// group.spawn("worker-3", || 3);
+///
+/// let (results, panics) = group.join_all();
+/// assert_eq!(results, [1, 3]);
+/// assert_eq!(panics[0].name, "worker-2");
+/// assert_eq!(panics[0].message, "out of work");
+/// ```
+#[derive(Debug)]
+pub struct WorkerGroup<T> {
+    /// The handles of the workers, in spawn order. A worker that panicked returns `None`
+    handles: Vec<JoinHandle<Option<T>>>,
+    /// The sending side of the panic channel, cloned into each worker, until the group
+    /// is closed
+    panic_sender: Option<mpsc::Sender<WorkerPanic>>,
+    /// The receiving side of the panic channel
+    panic_receiver: mpsc::Receiver<WorkerPanic>,
+}
+
+impl<T> WorkerGroup<T>
+where
+    T: Send + 'static,
+{
+    /// Creates an empty group.
+    pub fn new() -> Self {
+        let (panic_sender, panic_receiver) = mpsc::channel();
+        Self { handles: Vec::new(), panic_sender: Some(panic_sender), panic_receiver }
+    }
+
+    /// Spawns a named worker thread in the group, with [`thread_spawn`].
+    ///
+    /// # Parameters
+    ///
+    /// * `name` - The name to assign to the thread.
+    /// * `f` - The function to execute in the new thread.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if thread creation fails, or if the group was
+    /// [closed](WorkerGroup::close).
+    pub fn spawn<F>(&mut self, name: &str, f: F)
+    where
+        F: FnOnce() -> T,
+        F: Send + 'static,
+    {
+        let sender = self.panic_sender.clone().expect("cannot spawn in a closed WorkerGroup");
+        let worker_name = name.to_string();
+        let handle = thread_spawn(name, move || {
+            match panic::catch_unwind(AssertUnwindSafe(f)) {
+                Ok(result) => Some(result),
+                Err(payload) => {
+                    let message = panic_message(payload.as_ref());
+                    // The group may have been dropped, leaving nobody to report to
+                    let _ = sender.send(WorkerPanic { name: worker_name, message });
+                    None
+                }
+            }
+        });
+        self.handles.push(handle);
+    }
+
+    /// Returns the channel receiving the panics of the workers, as they happen.
+    ///
+    /// Until the group is [closed](WorkerGroup::close), the group itself keeps the channel
+    /// open, so `recv` or iterating over the channel blocks forever once no panic is
+    /// pending: use `try_recv` or `recv_timeout` instead. Once the group is closed, the
+    /// channel disconnects when every worker has finished, so iterating ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::WorkerGroup;
+    ///
+    /// let mut group = WorkerGroup::new();
+    /// group.spawn("ok", || ());
+    /// group.spawn("failing", || panic!("failed"));
+    /// group.close();
+    ///
+    /// let panics: Vec<_> = group.panics().iter().collect();
+    /// assert_eq!(panics.len(), 1);
+    /// assert_eq!(panics[0].name, "failing");
+    /// ```
+    pub fn panics(&self) -> &mpsc::Receiver<WorkerPanic> {
+        &self.panic_receiver
+    }
+
+    /// Closes the group to new workers, so the channel of [`WorkerGroup::panics`]
+    /// disconnects once the workers already spawned have finished.
+    ///
+    /// Spawning in a closed group panics. Closing it again does nothing.
+    pub fn close(&mut self) {
+        self.panic_sender = None;
+    }
+
+    /// Waits for every worker to finish.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the results of the workers that completed, in spawn order, and of the
+    /// panics that weren't received from [`WorkerGroup::panics`] yet, in the order they
+    /// happened.
+    pub fn join_all(self) -> (Vec<T>, Vec<WorkerPanic>) {
+        let results = self.handles.into_iter()
+            // Panics are caught within the workers, so joining can't fail
+            .filter_map(|handle| handle.join().ok().flatten())
+            .collect();
+        let panics = self.panic_receiver.try_iter().collect();
+        (results, panics)
+    }
+}
+
+impl<T> Default for WorkerGroup<T>
+where
+    T: Send + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Extracts the message of a panic payload, which is a `&str` or a `String` for panics
+/// raised with `panic!`.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "<non-string panic payload>".to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,4 +478,104 @@ mod tests {
         // Verify the thread returned the expected value
         assert_eq!(handle.join().unwrap(), "thread result");
     }
+
+    #[test]
+    fn test_thread_spawn_numbered() {
+        reset_thread_counter();
+
+        let handles: Vec<_> = (0..3)
+            .map(|_| thread_spawn_numbered("worker", || thread::current().name().map(String::from)))
+            .collect();
+        let names: Vec<_> = handles.into_iter()
+            .map(|handle| handle.join().unwrap().unwrap())
+            .collect();
+
+        assert_eq!(names, ["worker-1", "worker-2", "worker-3"]);
+    }
+
+    #[test]
+    fn test_thread_spawn_with_current_span() {
+        use crate::test_support::{captured_subscriber, CapturedWriter};
+
+        let writer = CapturedWriter::default();
+        tracing::subscriber::with_default(captured_subscriber(&writer), || {
+            let span = tracing::info_span!("request", request_id = 42);
+            let _entered = span.enter();
+
+            thread_spawn_with_current_span("span-thread", || tracing::info!("from the child"))
+                .join()
+                .unwrap();
+        });
+
+        let logs = writer.contents();
+        assert!(logs.contains("from the child"));
+        assert!(logs.contains("request{request_id=42}"));
+    }
+
+    #[test]
+    fn test_worker_group_reports_panics() {
+        let mut group = WorkerGroup::new();
+        for i in 0..5 {
+            group.spawn(&format!("group-worker-{i}"), move || {
+                if i == 2 {
+                    panic!("worker {i} failed");
+                }
+                thread::current().name().unwrap().to_string()
+            });
+        }
+
+        let (results, panics) = group.join_all();
+        assert_eq!(results, ["group-worker-0", "group-worker-1", "group-worker-3", "group-worker-4"]);
+        assert_eq!(panics, [WorkerPanic { name: "group-worker-2".to_string(), message: "worker 2 failed".to_string() }]);
+    }
+
+    #[test]
+    fn test_worker_group_panics_channel() {
+        let mut group = WorkerGroup::new();
+        group.spawn("static-panic", || panic!("static message"));
+        group.spawn("payload-panic", || std::panic::panic_any(42));
+
+        let mut received: Vec<WorkerPanic> = (0..2).map(|_| group.panics().recv().unwrap()).collect();
+        received.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(received[0].message, "<non-string panic payload>");
+        assert_eq!(received[1].message, "static message");
+
+        // Panics already received aren't reported again
+        let (results, panics): (Vec<()>, _) = group.join_all();
+        assert!(results.is_empty());
+        assert!(panics.is_empty());
+    }
+
+    #[test]
+    fn test_worker_group_close() {
+        let mut group = WorkerGroup::new();
+        for i in 0..4 {
+            group.spawn(&format!("close-worker-{i}"), move || {
+                if i % 2 == 1 {
+                    panic!("worker {i} failed");
+                }
+                i
+            });
+        }
+        group.close();
+        group.close();
+
+        // The channel disconnects once every worker is done, ending the iteration
+        let mut names: Vec<String> = group.panics().iter().map(|p| p.name).collect();
+        names.sort();
+        assert_eq!(names, ["close-worker-1", "close-worker-3"]);
+        assert!(matches!(group.panics().recv(), Err(mpsc::RecvError)));
+
+        let (results, panics) = group.join_all();
+        assert_eq!(results, [0, 2]);
+        assert!(panics.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "closed WorkerGroup")]
+    fn test_worker_group_spawn_after_close() {
+        let mut group = WorkerGroup::new();
+        group.close();
+        group.spawn("late", || ());
+    }
 }
@@ -29,7 +29,12 @@
 //! ```
 
 use std::fmt::Display;
+use std::time::Instant;
+
 use tracing::{event, Level};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{reload, Registry};
 
 /// A trait for converting a `Result` into an `Option` while logging any errors.
 ///
@@ -84,19 +89,75 @@ where
             Ok(value) => Some(value),
             Err(err) => {
                 // Log the error message at the specified level
-                match level {
-                    Level::TRACE => event!(Level::TRACE, "{}", err),
-                    Level::DEBUG => event!(Level::DEBUG, "{}", err),
-                    Level::INFO => event!(Level::INFO, "{}", err),
-                    Level::WARN => event!(Level::WARN, "{}", err),
-                    Level::ERROR => event!(Level::ERROR, "{}", err),
-                }
+                log_at(level, err);
                 None
             }
         }
     }
 }
 
+/// Logs `message` at a level only known at runtime.
+fn log_at(level: Level, message: impl Display) {
+    // The `event!` macro needs a constant level
+    match level {
+        Level::TRACE => event!(Level::TRACE, "{}", message),
+        Level::DEBUG => event!(Level::DEBUG, "{}", message),
+        Level::INFO => event!(Level::INFO, "{}", message),
+        Level::WARN => event!(Level::WARN, "{}", message),
+        Level::ERROR => event!(Level::ERROR, "{}", message),
+    }
+}
+
+/// Runs a function and logs how long it took.
+///
+/// Once `f` completes, a `"<label> took <ms>ms"` line is logged at the given level.
+/// The duration is logged from a drop guard, so it is reported even if `f` panics.
+///
+/// # Parameters
+///
+/// * `level` - The log level at which to log the duration.
+/// * `label` - A label identifying the timed block in the log line.
+/// * `f` - The function to time.
+///
+/// # Returns
+///
+/// The value returned by `f`.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "tracing-subscriber")]
+/// # {
+/// use cutoff_common::logging::timed;
+/// use tracing::Level;
+///
+/// // Logs something like "sum took 0.012ms" at DEBUG level
+/// let sum = timed(Level::DEBUG, "sum", || (1..=100).sum::<u32>());
+/// assert_eq!(sum, 5050);
+/// # }
+/// ```
+pub fn timed<F, R>(level: Level, label: &str, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let _guard = TimedGuard { level, label, start: Instant::now() };
+    f()
+}
+
+/// Logs the time elapsed since its creation when dropped.
+struct TimedGuard<'a> {
+    level: Level,
+    label: &'a str,
+    start: Instant,
+}
+
+impl Drop for TimedGuard<'_> {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        log_at(self.level, format_args!("{} took {:.3}ms", self.label, elapsed.as_secs_f64() * 1000.0));
+    }
+}
+
 /// Initializes the logging infrastructure with a standardized configuration.
 ///
 /// This function sets up the `tracing_subscriber` with a compact format and
@@ -146,3 +207,147 @@ pub fn init_logging(max_level: Level) {
         // .with_ansi(false) // Uncomment to disable colors
         .init();
 }
+
+/// A handle to change the maximum log level of the subscriber installed by
+/// [`init_logging_reloadable`] while the application is running.
+///
+/// The handle is cheap to clone, so it can be shared, e.g. with an admin endpoint.
+#[derive(Clone, Debug)]
+pub struct LevelHandle {
+    inner: reload::Handle<LevelFilter, Registry>,
+}
+
+impl LevelHandle {
+    /// Changes the maximum log level, taking effect for the events logged from now on.
+    ///
+    /// # Parameters
+    ///
+    /// * `level` - The new maximum log level.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the level was changed.
+    /// * `Err(reload::Error)` - If the subscriber was dropped, or its filter lock poisoned.
+    pub fn set_level(&self, level: Level) -> Result<(), reload::Error> {
+        self.inner.reload(LevelFilter::from_level(level))
+    }
+
+    /// Returns the current maximum log level.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Level)` - The maximum log level.
+    /// * `None` - If the subscriber was dropped.
+    pub fn level(&self) -> Option<Level> {
+        self.inner.clone_current().and_then(|filter| filter.into_level())
+    }
+}
+
+/// Creates a level filter that can be changed through the returned handle.
+fn reloadable_filter(initial: Level) -> (reload::Layer<LevelFilter, Registry>, LevelHandle) {
+    let (filter, inner) = reload::Layer::new(LevelFilter::from_level(initial));
+    (filter, LevelHandle { inner })
+}
+
+/// Initializes the logging infrastructure like [`init_logging`], with a maximum log level
+/// that can be changed at runtime.
+///
+/// # Parameters
+///
+/// * `initial` - The maximum log level to display until it's changed.
+///
+/// # Returns
+///
+/// A [`LevelHandle`] to change the maximum log level.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "tracing-subscriber")]
+/// # {
+/// use cutoff_common::logging::init_logging_reloadable;
+/// use tracing::Level;
+///
+/// let handle = init_logging_reloadable(Level::INFO);
+/// tracing::debug!("This debug message won't be displayed");
+///
+/// handle.set_level(Level::DEBUG).unwrap();
+/// tracing::debug!("This one will");
+/// # }
+/// ```
+///
+/// # Note
+///
+/// As with [`init_logging`], this sets the global default subscriber, and should only
+/// be called once.
+pub fn init_logging_reloadable(initial: Level) -> LevelHandle {
+    let (filter, handle) = reloadable_filter(initial);
+    tracing_subscriber::registry()
+        .with(filter)
+        // The same format as `init_logging`
+        .with(tracing_subscriber::fmt::layer()
+            .compact()
+            .with_target(true)
+            .with_thread_names(true)
+            .with_thread_ids(false))
+        .init();
+    handle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{captured_subscriber, CapturedWriter};
+    use std::panic;
+
+    #[test]
+    fn test_timed() {
+        let writer = CapturedWriter::default();
+        let result = tracing::subscriber::with_default(captured_subscriber(&writer), || {
+            timed(Level::INFO, "computation", || 42)
+        });
+
+        assert_eq!(result, 42);
+        let logs = writer.contents();
+        assert!(logs.contains("INFO"));
+        assert!(logs.contains("computation took "));
+        assert!(logs.contains("ms"));
+    }
+
+    #[test]
+    fn test_timed_logs_on_panic() {
+        let writer = CapturedWriter::default();
+        let result = tracing::subscriber::with_default(captured_subscriber(&writer), || {
+            panic::catch_unwind(|| timed(Level::WARN, "failing", || panic!("boom")))
+        });
+
+        assert!(result.is_err());
+        assert!(writer.contents().contains("failing took "));
+    }
+
+    #[test]
+    fn test_reloadable_level() {
+        let writer = CapturedWriter::default();
+        let (filter, handle) = reloadable_filter(Level::WARN);
+        let layer_writer = writer.clone();
+        let subscriber = tracing_subscriber::registry()
+            .with(filter)
+            .with(tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(move || layer_writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("filtered out");
+            assert_eq!(handle.level(), Some(Level::WARN));
+
+            handle.set_level(Level::INFO).unwrap();
+            assert_eq!(handle.level(), Some(Level::INFO));
+            tracing::info!("let through");
+            tracing::debug!("still filtered out");
+        });
+
+        let logs = writer.contents();
+        assert!(!logs.contains("filtered out"), "{logs}");
+        assert!(logs.contains("let through"), "{logs}");
+    }
+}
@@ -0,0 +1,38 @@
+//! Fixtures shared by the unit tests of several modules.
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use tracing::Level;
+
+/// A writer collecting everything logged into a shared buffer.
+#[derive(Clone, Default)]
+pub(crate) struct CapturedWriter(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedWriter {
+    /// Returns everything written so far.
+    pub(crate) fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl io::Write for CapturedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns a subscriber logging every level, without colors, into `writer`.
+pub(crate) fn captured_subscriber(writer: &CapturedWriter) -> impl tracing::Subscriber {
+    let writer = writer.clone();
+    tracing_subscriber::fmt()
+        .with_max_level(Level::TRACE)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish()
+}
@@ -31,22 +31,68 @@
 //! assert_eq!(urn.to_string(), "urn:example:resource/path?key=value#section");
 //! ```
 
-use std::fmt::{Display, Formatter};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Write};
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock};
 
 use derive_builder::Builder;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use regex::Regex;
 use thiserror::Error;
 use url::Url;
 
+/// The settings of a `UrnBuilder` that aren't components of the URN.
+///
+/// derive_builder has no builder-only fields: they live in the builder's slot for the
+/// [`BuilderOnly`] field of `Urn`.
+#[derive(Debug, Clone)]
+struct UrnBuilderSettings {
+    /// Whether `build` rejects invalid URNs
+    strict: bool,
+    /// The maximum number of path segments `build` accepts, if any
+    max_path_depth: Option<usize>,
+}
+
+impl Default for UrnBuilderSettings {
+    fn default() -> Self {
+        Self { strict: true, max_path_depth: None }
+    }
+}
+
+/// The empty value of a `Urn` field that only exists for its slot in `UrnBuilder`, which
+/// holds the [`UrnBuilderSettings`].
+#[derive(Debug, Clone, Copy, Default)]
+struct BuilderOnly;
+
+/// The NID of the URNs identifying Cutoff resources.
+const CUTOFF_NID: &str = "cutoff";
+/// The NSS of the URNs identifying Cutoff projects.
+const CUTOFF_PROJECT: &str = "project";
+/// The NSS of the URNs identifying Cutoff tracks.
+const CUTOFF_TRACK: &str = "track";
+
 /// A regular expression pattern for parsing URNs.
 /// The pattern matches URNs in the format: urn:<nid>:<nss>[/<path>][?<query>][#<fragment>]
 static URN_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"^([A-Za-z0-9\-._]+):([A-Za-z0-9.\-_:]+)(?:/([A-Za-z0-9/\-]*))?$")
+    Regex::new(r"^([A-Za-z0-9\-._]+):((?:[A-Za-z0-9.\-_:]|%[0-9A-Fa-f]{2})+)(?:/((?:[A-Za-z0-9/\-]|%[0-9A-Fa-f]{2})*))?$")
         .expect("Cannot compile the URN regular expression")
 });
 
+/// The characters encoded in a path segment: everything `URN_PATTERN` doesn't accept
+/// unencoded in a path, and `/`.
+const SEGMENT_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-');
+
+/// The part of `URN_PATTERN` matching the NSS and the optional path, used when the NID
+/// is already known.
+static NSS_PATH_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^((?:[A-Za-z0-9.\-_:]|%[0-9A-Fa-f]{2})+)(?:/((?:[A-Za-z0-9/\-]|%[0-9A-Fa-f]{2})*))?$")
+        .expect("Cannot compile the NSS regular expression")
+});
+
 /// Represents a Uniform Resource Name (URN).
 ///
 /// A URN is a URI that uses the "urn" scheme. It's designed to be globally unique and persistent
@@ -57,20 +103,78 @@ static URN_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
 /// * `nid`: Namespace Identifier
 /// * `nss`: Namespace Specific String
 /// * `path`: Optional path component
-/// * `query`: Optional query component
-/// * `fragment`: Optional fragment component
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Builder)]
+/// * `query`: Optional text after `?`, holding the r-component and q-component
+/// * `fragment`: Optional fragment component (the f-component of RFC 8141)
+///
+/// RFC 8141 splits the text after `?` into a resolution component (`?+`) and a query
+/// component (`?=`), available through [`Urn::rcomponent`] and [`Urn::qcomponent`].
+/// The text is stored as a whole, so URNs using a plain `?key=value` query keep
+/// round-tripping unchanged.
+///
+/// Components are stored as shared `Arc<str>`, so cloning a URN or deriving a new one
+/// with `with_query`/`with_fragment` doesn't copy the components that stay the same.
+///
+/// Components are stored percent-encoded, as in the string form: the builder encodes
+/// the characters a component doesn't allow, so a built URN equals the one parsed back
+/// from its string. Accessors such as [`Urn::decoded_nss`] decode them.
+///
+/// # Equality
+///
+/// `==` follows RFC 8141 and delegates to [`Urn::equals`]: the NID is compared
+/// case-insensitively, every other component is compared byte for byte. `Hash` is
+/// consistent with this, as it hashes the lowercased NID, so `urn:EXAMPLE:x` and
+/// `urn:example:x` are the same key in a `HashSet<Urn>` or `HashMap<Urn, _>`.
+///
+/// URNs differing only by their query or fragment are still different keys. To key
+/// collections by [lexical equivalence](Urn::is_lexically_equivalent) instead, which
+/// ignores both, wrap the URNs in a [`UrnKey`].
+///
+/// ```
+/// use cutoff_common::urn::Urn;
+/// use std::collections::HashSet;
+/// use std::str::FromStr;
+///
+/// let upper = Urn::from_str("urn:EXAMPLE:resource").unwrap();
+/// let lower = Urn::from_str("urn:example:resource").unwrap();
+/// assert_eq!(upper, lower);
+///
+/// let set: HashSet<Urn> = [upper, lower].into_iter().collect();
+/// assert_eq!(set.len(), 1);
+/// ```
+///
+/// # Strict and lenient building
+///
+/// The builder is strict by default: `build` fails on a URN that isn't
+/// [valid](Urn::is_valid), or whose NID isn't valid per RFC 8141. Call `strict(false)`
+/// to build anything, for instance to migrate legacy data or assemble a
+/// [relative](Urn::is_relative) URN.
+///
+/// ```
+/// use cutoff_common::urn::Urn;
+///
+/// assert!(Urn::builder().nid("").nss("resource").build().is_err());
+/// assert!(Urn::builder().nid("").nss("resource").strict(false).build().is_ok());
+/// ```
+#[derive(Debug, Clone, Builder)]
+#[builder(build_fn(private, name = "build_unencoded", validate = "Self::validate"))]
 pub struct Urn {
     #[builder(setter(into))]
-    nid: String,
+    nid: Arc<str>,
     #[builder(setter(into))]
-    nss: String,
+    nss: Arc<str>,
     #[builder(setter(into, strip_option), default)]
-    path: Option<String>,
+    path: Option<Arc<str>>,
     #[builder(setter(into, strip_option), default)]
-    query: Option<String>,
+    query: Option<Arc<str>>,
     #[builder(setter(into, strip_option), default)]
-    fragment: Option<String>,
+    fragment: Option<Arc<str>>,
+    /// The exact string this URN was parsed from, if it was retained.
+    #[builder(setter(skip))]
+    raw: Option<Arc<str>>,
+    /// Always empty: the builder's slot for this field holds its settings.
+    #[allow(dead_code)]
+    #[builder(setter(custom), field(ty = "UrnBuilderSettings", build = "BuilderOnly"))]
+    builder_settings: BuilderOnly,
 }
 
 impl Urn {
@@ -78,381 +182,3394 @@ impl Urn {
         UrnBuilder::default()
     }
 
+    /// Returns a builder pre-populated with all the components of this URN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let urn = Urn::from_str("urn:example:resource/path#section").unwrap();
+    /// let derived = urn.to_builder().fragment("other").build().unwrap();
+    /// assert_eq!(derived.to_string(), "urn:example:resource/path#other");
+    /// ```
+    pub fn to_builder(&self) -> UrnBuilder {
+        UrnBuilder {
+            nid: Some(self.nid.clone()),
+            nss: Some(self.nss.clone()),
+            path: Some(self.path.clone()),
+            query: Some(self.query.clone()),
+            fragment: Some(self.fragment.clone()),
+            ..UrnBuilder::default()
+        }
+    }
+
     /// Returns the Namespace Identifier (NID) of the URN.
     pub fn nid(&self) -> &str { &self.nid }
 
-    /// Returns the Namespace Specific String (NSS) of the URN.
+    /// Returns the Namespace Specific String (NSS) of the URN, percent-encoded as in its
+    /// string form. See [`Urn::decoded_nss`] for the decoded value.
     pub fn nss(&self) -> &str { &self.nss }
 
+    /// Returns the NSS with its `%XX` sequences decoded.
+    ///
+    /// Decoded bytes that aren't valid UTF-8 are replaced with `U+FFFD`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let urn = Urn::from_str("urn:example:foo%20bar%2Fbaz").unwrap();
+    /// assert_eq!(urn.nss(), "foo%20bar%2Fbaz");
+    /// assert_eq!(urn.decoded_nss(), "foo bar/baz");
+    /// ```
+    pub fn decoded_nss(&self) -> Cow<'_, str> {
+        percent_decode_str(&self.nss).decode_utf8_lossy()
+    }
+
     /// Returns the optional path component of the URN, if present.
     pub fn path(&self) -> Option<&str> { self.path.as_deref() }
 
-    /// Returns the optional query component of the URN, if present.
-    pub fn query(&self) -> Option<&str> { self.query.as_deref() }
-
-    /// Returns the optional fragment component of the URN, if present.
-    pub fn fragment(&self) -> Option<&str> { self.fragment.as_deref() }
-
-    /// Checks if the URN is valid according to RFC 8141.
-    pub fn is_valid(&self) -> bool {
-        // This is a simplified check. A full implementation would need to consider
-        // all rules specified in RFC 8141.
-        !self.nid.is_empty() && !self.nss.is_empty()
-    }
-
-    /// Converts the URN to a URL, if possible.
-    pub fn to_url(&self) -> Option<Url> {
-        Url::parse(&self.to_string()).ok()
+    /// Returns the percent-decoded segments of the path, if the URN has a path.
+    ///
+    /// Each `/`-separated segment is decoded independently, so an encoded `%2F` yields
+    /// a literal slash inside its segment rather than splitting it. Empty segments are
+    /// skipped. Parsing rejects malformed percent sequences, so decoding only falls back
+    /// to lossy UTF-8 conversion for URNs assembled through the builder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let urn = Urn::from_str("urn:example:x/a%20b/c%2Fd").unwrap();
+    /// let segments: Vec<_> = urn.path_segments_decoded().unwrap().collect();
+    /// assert_eq!(segments, ["a b", "c/d"]);
+    /// ```
+    pub fn path_segments_decoded(&self) -> Option<impl Iterator<Item = Cow<'_, str>>> {
+        self.path_segments()
+            .map(|segments| segments.map(|segment| percent_decode_str(segment).decode_utf8_lossy()))
     }
 
-    /// Compares two URNs for equality, ignoring case sensitivity in the scheme and namespace identifier.
-    pub fn equals(&self, other: &Self) -> bool {
-        self.nid.to_lowercase() == other.nid.to_lowercase() &&
-            self.nss == other.nss &&
-            self.path == other.path &&
-            self.query == other.query &&
-            self.fragment == other.fragment
+    /// Returns an iterator over the `/`-separated segments of the path, still
+    /// percent-encoded, or `None` if the URN has no path.
+    ///
+    /// Only real `/` separators split the path: an encoded `%2F` stays inside its
+    /// segment. Empty segments, such as the one after a trailing slash, are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let urn = Urn::from_str("urn:example:x/path/to/resource/").unwrap();
+    /// let segments: Vec<_> = urn.path_segments().unwrap().collect();
+    /// assert_eq!(segments, ["path", "to", "resource"]);
+    /// ```
+    pub fn path_segments(&self) -> Option<impl Iterator<Item = &str>> {
+        self.path.as_deref()
+            .map(|path| path.split('/').filter(|segment| !segment.is_empty()))
     }
 
-    /// Normalizes the URN by converting the scheme and namespace identifier to lowercase.
-    pub fn normalize(&self) -> Self {
+    /// Creates a new URN with a segment appended to its path.
+    ///
+    /// The segment is taken literally and fully percent-encoded, so it stays a single
+    /// segment whatever it contains: `a/b` becomes `a%2Fb`, and a `%` is encoded too.
+    /// A trailing slash in the path doesn't produce an empty segment.
+    ///
+    /// # Parameters
+    ///
+    /// * `segment` - The segment to append, not encoded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let urn = Urn::from_str("urn:example:x/path").unwrap();
+    /// assert_eq!(urn.push_segment("to resource").to_string(), "urn:example:x/path/to%20resource");
+    /// ```
+    pub fn push_segment(&self, segment: &str) -> Urn {
+        let encoded = utf8_percent_encode(segment, SEGMENT_ENCODE_SET);
+        let path = match self.path.as_deref().map(|p| p.trim_end_matches('/')) {
+            Some(path) if !path.is_empty() => format!("{path}/{encoded}"),
+            _ => encoded.to_string(),
+        };
         Urn {
-            nid: self.nid.to_lowercase(),
+            nid: self.nid.clone(),
             nss: self.nss.clone(),
-            path: self.path.clone(),
+            path: Some(Arc::from(path)),
             query: self.query.clone(),
             fragment: self.fragment.clone(),
+            raw: None,
+            builder_settings: BuilderOnly,
         }
     }
 
-    /// Creates a new URN with the given query string.
-    pub fn with_query(&self, query: Option<&str>) -> Self {
-        Urn {
-            nid: self.nid.clone(),
-            nss: self.nss.clone(),
-            path: self.path.clone(),
-            query: query.map(String::from),
-            fragment: self.fragment.clone(),
+    /// Checks whether two URNs have equivalent paths once each segment is percent-decoded.
+    ///
+    /// Segments are decoded independently, so `a%2Fb` (one segment) is never equivalent
+    /// to `a/b` (two segments), while `a%2Fb` and `a%2fb` are.
+    pub fn is_path_equivalent(&self, other: &Self) -> bool {
+        match (self.path_segments_decoded(), other.path_segments_decoded()) {
+            (Some(segments), Some(other_segments)) => segments.eq(other_segments),
+            (None, None) => true,
+            _ => false,
         }
     }
 
-    /// Creates a new URN with the given fragment.
-    pub fn with_fragment(&self, fragment: Option<&str>) -> Self {
-        Urn {
-            nid: self.nid.clone(),
-            nss: self.nss.clone(),
-            path: self.path.clone(),
-            query: self.query.clone(),
-            fragment: fragment.map(String::from),
-        }
-    }
+    /// Returns the whole text after `?`, if present, without telling the r-component and
+    /// q-component apart.
+    #[deprecated(note = "use `rcomponent` and `qcomponent`, which follow RFC 8141")]
+    pub fn query(&self) -> Option<&str> { self.query.as_deref() }
 
-    /// Creates a new URN without the query component.
-    pub fn without_query(&self) -> Self {
-        self.with_query(None)
+    /// Returns the r-component of the URN (RFC 8141), the text following `?+`, if present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let urn = Urn::from_str("urn:example:foo?+res?=k=v#frag").unwrap();
+    /// assert_eq!(urn.rcomponent(), Some("res"));
+    /// assert_eq!(urn.qcomponent(), Some("k=v"));
+    /// assert_eq!(urn.fragment(), Some("frag"));
+    /// ```
+    pub fn rcomponent(&self) -> Option<&str> {
+        self.query.as_deref().and_then(|q| split_rq_components(q).0)
     }
 
-
-    /// Creates a new URN without the fragment component.
-    pub fn without_fragment(&self) -> Self {
-        self.with_fragment(None)
+    /// Returns the q-component of the URN (RFC 8141), the text following `?=`, if present.
+    ///
+    /// A plain `?key=value` query, which isn't an RFC 8141 q-component, isn't returned.
+    pub fn qcomponent(&self) -> Option<&str> {
+        self.query.as_deref().and_then(|q| split_rq_components(q).1)
     }
 
-    /// Parses the query string into a key-value map.
-    pub fn parse_query(&self) -> Option<std::collections::HashMap<String, String>> {
-        self.query.as_ref().map(|q| {
-            url::form_urlencoded::parse(q.as_bytes())
-                .into_owned()
-                .collect()
-        })
-    }
+    /// Returns the optional fragment component of the URN, if present.
+    pub fn fragment(&self) -> Option<&str> { self.fragment.as_deref() }
 
-    /// Checks if two URNs are lexically equivalent according to RFC 8141.
-    pub fn is_lexically_equivalent(&self, other: &Self) -> bool {
-        let norm_self = self.normalize();
-        let norm_other = other.normalize();
+    /// Returns the exact string the URN was parsed from, if it was parsed with
+    /// [`Urn::from_str_retaining`]. URNs that are built or derived from another one
+    /// don't have a raw form.
+    pub fn raw(&self) -> Option<&str> { self.raw.as_deref() }
 
-        norm_self.nid == norm_other.nid &&
-            norm_self.nss == norm_other.nss &&
-            norm_self.path == norm_other.path
-        // Note: query and fragment are not considered for lexical equivalence
+    /// Parses a URN like `from_str`, but also keeps the exact input string.
+    ///
+    /// This is useful to log what a producer actually sent, including non-canonical
+    /// formatting that the `Display` form doesn't reproduce. The raw string takes no
+    /// part in equality or hashing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    ///
+    /// let urn = Urn::from_str_retaining("urn:example:resource%2fpath").unwrap();
+    /// assert_eq!(urn.raw(), Some("urn:example:resource%2fpath"));
+    /// ```
+    pub fn from_str_retaining(urn_string: &str) -> Result<Self, UrnFormatError> {
+        let mut urn = Self::from_str(urn_string)?;
+        urn.raw = Some(Arc::from(urn_string));
+        Ok(urn)
     }
-}
 
-impl FromStr for Urn {
-    type Err = UrnFormatError;
+    /// Parses a URN whose NID is expected to be `expected_nid`, bypassing most of the
+    /// generic parsing.
+    ///
+    /// When the input starts with `urn:<expected_nid>:`, the NID is taken as is without
+    /// being validated, and the rest is split by hand, only using a regular expression
+    /// for the NSS and path. Anything that doesn't fit this fast path, including every
+    /// malformed input, goes through `from_str`, so both always give the same result as
+    /// long as `expected_nid` is itself a valid NID.
+    ///
+    /// This is meant for trusted, homogeneous inputs sharing a known NID.
+    ///
+    /// # Parameters
+    ///
+    /// * `urn_string` - The string to parse.
+    /// * `expected_nid` - The NID most inputs are expected to use, compared byte for byte.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    ///
+    /// let urn = Urn::from_str_fast("urn:example:resource/path?k=v", "example").unwrap();
+    /// assert_eq!(urn.nss(), "resource");
+    /// assert_eq!(urn.path(), Some("path"));
+    ///
+    /// // Other NIDs are still parsed, only more slowly
+    /// let urn = Urn::from_str_fast("urn:other:resource", "example").unwrap();
+    /// assert_eq!(urn.nid(), "other");
+    /// ```
+    pub fn from_str_fast(urn_string: &str, expected_nid: &str) -> Result<Self, UrnFormatError> {
+        match Self::parse_with_nid(urn_string, expected_nid) {
+            Some(urn) => Ok(urn),
+            None => Self::from_str(urn_string),
+        }
+    }
 
-    fn from_str(urn_string: &str) -> Result<Self, Self::Err> {
-        // Check if the string starts with "urn:"
-        if !urn_string.starts_with("urn:") {
-            return Err(UrnFormatError::UrnSchemeExpected);
+    /// The fast path of `from_str_fast`, returning `None` whenever the input needs the
+    /// full parser.
+    fn parse_with_nid(urn_string: &str, expected_nid: &str) -> Option<Self> {
+        if expected_nid.is_empty() {
+            return None;
         }
+        let rest = urn_string.strip_prefix("urn:")?
+            .strip_prefix(expected_nid)?
+            .strip_prefix(':')?;
 
-        let url = Url::parse(urn_string)
-            .map_err(|_| UrnFormatError::InvalidUrn)?;
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (rest, None),
+        };
+        if fragment.is_some_and(|f| find_invalid_byte(f, is_fragment_byte).is_some()) {
+            return None;
+        }
 
-        if let Some(captures) = URN_PATTERN.captures(url.path()) {
-            let urn = Urn {
-                nid: captures.get(1).map_or("", |m| m.as_str()).to_string(),
-                nss: captures.get(2).map_or("", |m| m.as_str()).to_string(),
-                path: captures.get(3).map(|m| m.as_str().to_string()),
-                query: url.query().map(|s| s.to_string()),
-                fragment: url.fragment().map(|s| s.to_string()),
-            };
-            Ok(urn)
-        } else {
-            Err(UrnFormatError::InvalidUrn)
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (rest, None),
+        };
+        // The full parser would encode anything else
+        if query.is_some_and(|q| find_invalid_byte(q, is_fragment_byte).is_some()) {
+            return None;
         }
+
+        let captures = NSS_PATH_PATTERN.captures(rest)?;
+        Some(Urn {
+            nid: expected_nid.into(),
+            nss: captures.get(1).map_or("", |m| m.as_str()).into(),
+            path: captures.get(2).map(|m| m.as_str().into()),
+            query: query.map(Arc::from),
+            fragment: fragment.map(Arc::from),
+            raw: None,
+            builder_settings: BuilderOnly,
+        })
     }
-}
 
-impl Display for Urn {
-    /// Formats the URN as a string.
+    /// Builds a URN from its individual, already encoded components, validating each one.
+    ///
+    /// This is the structured counterpart of parsing a string, for instance to build a
+    /// URN from form fields: the error names the first invalid component, and where in
+    /// it the problem is, so the offending input can be highlighted.
+    ///
+    /// Each component is checked against the grammar `from_str` accepts for it:
+    ///
+    /// * `nid` - 2 to 32 letters, digits or hyphens, not starting or ending with a hyphen.
+    /// * `nss` - Non-empty, made of letters, digits, `-._:` and `%XX` sequences.
+    /// * `path` - Letters, digits, `-/` and `%XX` sequences.
+    /// * `query` and `fragment` - RFC 3986 pchars, `/`, `?` and `%XX` sequences.
     ///
     /// # Returns
     ///
-    /// A string representation of the URN in the format:
-    /// urn:<nid>:<nss>[/<path>][?<query>][#<fragment>]
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "urn:{}:{}", self.nid, self.nss)?;
-        if let Some(path) = &self.path {
-            write!(f, "/{}", path)?;
+    /// * `Ok(Urn)` - The URN, if every component is valid.
+    /// * `Err(UrnError::InvalidComponent)` - The first invalid component, and the byte
+    ///   offset of the problem within it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::{Urn, UrnComponent, UrnError};
+    ///
+    /// let urn = Urn::from_components("example", "resource", Some("a/b"), None, Some("top")).unwrap();
+    /// assert_eq!(urn.to_string(), "urn:example:resource/a/b#top");
+    ///
+    /// let err = Urn::from_components("example", "resource", Some("a b"), None, None).unwrap_err();
+    /// assert!(matches!(err, UrnError::InvalidComponent { component: UrnComponent::Path, position: 1 }));
+    /// ```
+    pub fn from_components(
+        nid: &str,
+        nss: &str,
+        path: Option<&str>,
+        query: Option<&str>,
+        fragment: Option<&str>,
+    ) -> Result<Urn, UrnError> {
+        let invalid = |component, position| UrnError::InvalidComponent { component, position };
+
+        if let Some(position) = find_invalid_nid_byte(nid) {
+            return Err(invalid(UrnComponent::Nid, position));
         }
-        if let Some(query) = &self.query {
-            write!(f, "?{}", query)?;
+        if nss.is_empty() {
+            return Err(invalid(UrnComponent::Nss, 0));
         }
-        if let Some(fragment) = &self.fragment {
-            write!(f, "#{}", fragment)?;
+        let checks = [
+            (UrnComponent::Nss, Some(nss), is_nss_byte as fn(u8) -> bool),
+            (UrnComponent::Path, path, is_path_byte),
+            (UrnComponent::Query, query, is_fragment_byte),
+            (UrnComponent::Fragment, fragment, is_fragment_byte),
+        ];
+        for (component, value, allowed) in checks {
+            if let Some(position) = value.and_then(|v| find_invalid_byte(v, allowed)) {
+                return Err(invalid(component, position));
+            }
         }
-        Ok(())
-    }
-}
 
-#[derive(Error, Debug)]
-pub enum UrnFormatError {
-    /// Returned when the input string doesn't start with the "urn:" scheme.
-    #[error("Invalid URN: URN scheme expected, but not found")]
-    UrnSchemeExpected,
+        Ok(Urn {
+            nid: Arc::from(nid),
+            nss: Arc::from(nss),
+            path: path.map(Arc::from),
+            query: query.map(Arc::from),
+            fragment: fragment.map(Arc::from),
+            raw: None,
+            builder_settings: BuilderOnly,
+        })
+    }
 
-    /// Returned when the input string doesn't match the expected URN format.
-    #[error("Invalid URN: unrecognizable URN format")]
-    InvalidUrn,
-}
+    /// Expands a URN template, replacing its `{name}` placeholders with the values of
+    /// the variables.
+    ///
+    /// This is a small subset of URI templates (RFC 6570): only simple `{name}`
+    /// placeholders are supported, without operators or modifiers. Values are fully
+    /// percent-encoded, like [`Urn::push_segment`] does, so a value containing `/` stays
+    /// within its segment. The expanded string is then parsed as a URN.
+    ///
+    /// # Parameters
+    ///
+    /// * `template` - The template, e.g. `urn:cutoff:track/{id}/waveform`.
+    /// * `vars` - The values of the variables, by name.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Urn)` - The expanded URN.
+    /// * `Err(UrnError::MissingVariable)` - If a placeholder names a variable missing
+    ///   from `vars`.
+    /// * `Err(UrnError::Validation)` - If a `{` isn't closed.
+    /// * `Err(UrnError::Format)` - If the expanded string isn't a valid URN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::collections::HashMap;
+    ///
+    /// let vars = HashMap::from([("id".to_string(), "my track".to_string())]);
+    /// let urn = Urn::expand_template("urn:cutoff:track/{id}/waveform", &vars).unwrap();
+    /// assert_eq!(urn.to_string(), "urn:cutoff:track/my%20track/waveform");
+    /// ```
+    pub fn expand_template(template: &str, vars: &HashMap<String, String>) -> Result<Urn, UrnError> {
+        let mut expanded = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            expanded.push_str(&rest[..open]);
+            let placeholder = &rest[open + 1..];
+            let close = placeholder.find('}').ok_or_else(|| {
+                UrnError::Validation(format!("unclosed placeholder in template `{template}`"))
+            })?;
+            let name = &placeholder[..close];
+            let value = vars.get(name).ok_or_else(|| UrnError::MissingVariable(name.to_string()))?;
+            expanded.extend(utf8_percent_encode(value, SEGMENT_ENCODE_SET));
+            rest = &placeholder[close + 1..];
+        }
+        expanded.push_str(rest);
 
-#[cfg(feature = "serde")]
-mod serde {
-    use crate::urn::Urn;
-    use serde::{Deserialize, Deserializer, Serialize, Serializer};
-    use std::str::FromStr;
+        Ok(Urn::from_str(&expanded)?)
+    }
 
-    impl Serialize for Urn {
-        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-        where
-            S: Serializer,
-        {
-            serializer.serialize_str(&self.to_string())
-        }
+    /// Checks if the URN is valid according to RFC 8141.
+    pub fn is_valid(&self) -> bool {
+        // This is a simplified check. A full implementation would need to consider
+        // all rules specified in RFC 8141.
+        !self.nid.is_empty() && !self.nss.is_empty()
     }
 
-    impl<'de> Deserialize<'de> for Urn {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: Deserializer<'de>,
-        {
-            let s = String::deserialize(deserializer)?;
-            Urn::from_str(&s)
-                .map_err(serde::de::Error::custom)
+    /// Checks the grammar of every component, and reports all the violations at once.
+    ///
+    /// The rules are the ones [`Urn::from_components`] enforces, which stops at the first
+    /// violation instead:
+    /// - the NID and NSS must not be empty
+    /// - the NID must be 2 to 32 characters long
+    /// - every component must only contain the characters RFC 8141 allows for it, or
+    ///   complete `%XX` sequences
+    ///
+    /// Only the first illegal character of each component is reported.
+    ///
+    /// # Returns
+    ///
+    /// The violations, in the order of the components in a URN. The list is empty when
+    /// the URN is valid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::{Urn, UrnComponent, UrnValidationError};
+    ///
+    /// let urn = Urn::builder().nid("x").nss("").strict(false).build().unwrap();
+    /// assert_eq!(urn.validate_all(), vec![
+    ///     UrnValidationError::InvalidLength { component: UrnComponent::Nid, len: 1 },
+    ///     UrnValidationError::Empty { component: UrnComponent::Nss },
+    /// ]);
+    /// ```
+    pub fn validate_all(&self) -> Vec<UrnValidationError> {
+        let mut errors = Vec::new();
+
+        if self.nid.is_empty() {
+            errors.push(UrnValidationError::Empty { component: UrnComponent::Nid });
+        } else {
+            if let Some(position) = find_invalid_nid_byte(&self.nid).filter(|&p| p < self.nid.len()) {
+                errors.push(UrnValidationError::IllegalCharacter { component: UrnComponent::Nid, position });
+            }
+            if !(2..=32).contains(&self.nid.len()) {
+                errors.push(UrnValidationError::InvalidLength { component: UrnComponent::Nid, len: self.nid.len() });
+            }
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        if self.nss.is_empty() {
+            errors.push(UrnValidationError::Empty { component: UrnComponent::Nss });
+        }
+        let checks = [
+            (UrnComponent::Nss, Some(&self.nss), is_nss_byte as fn(u8) -> bool),
+            (UrnComponent::Path, self.path.as_ref(), is_path_byte),
+            (UrnComponent::Query, self.query.as_ref(), is_fragment_byte),
+            (UrnComponent::Fragment, self.fragment.as_ref(), is_fragment_byte),
+        ];
+        for (component, value, allowed) in checks {
+            if let Some(position) = value.and_then(|v| find_invalid_byte(v, allowed)) {
+                errors.push(UrnValidationError::IllegalCharacter { component, position });
+            }
+        }
 
-    #[test]
-    fn test_from_string() {
-        let urn = Urn::from_str("urn:some_nid:foo.bar/hello?foo=bar&flip=flop#world").unwrap();
-        assert_eq!(urn.nid, "some_nid");
-        assert_eq!(urn.nss, "foo.bar");
-        assert_eq!(urn.path, Some("hello".to_string()));
-        assert_eq!(urn.query, Some("foo=bar&flip=flop".to_string()));
-        assert_eq!(urn.fragment, Some("world".to_string()));
+        errors
     }
 
-    #[test]
-    fn test_no_scheme() {
-        match Urn::from_str("some_nid:foo.bar/hello?foo=bar&flip=flop#world") {
-            Err(UrnFormatError::UrnSchemeExpected) => {}
-            _ => panic!("Expected UrnFormatError::UrnSchemeExpected"),
+    /// Validates the NSS with the validator registered for the URN's NID, if any.
+    ///
+    /// URNs whose NID has no registered validator are considered valid.
+    ///
+    /// # Parameters
+    ///
+    /// * `validators` - The registry of NID-specific validators.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the NSS passes validation, or no validator applies.
+    /// * `Err(UrnError::Validation)` - If the validator rejects the NSS.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::{NidValidatorRegistry, Urn};
+    /// use std::str::FromStr;
+    ///
+    /// let validators = NidValidatorRegistry::with_builtins();
+    ///
+    /// let urn = Urn::from_str("urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6").unwrap();
+    /// assert!(urn.validate_against(&validators).is_ok());
+    ///
+    /// let urn = Urn::from_str("urn:uuid:not-a-uuid").unwrap();
+    /// assert!(urn.validate_against(&validators).is_err());
+    /// ```
+    pub fn validate_against(&self, validators: &NidValidatorRegistry) -> Result<(), UrnError> {
+        match validators.get(&self.nid) {
+            Some(validator) => validator.validate_nss(&self.nss).map_err(UrnError::Validation),
+            None => Ok(()),
         }
     }
 
-    #[test]
+    /// Maps a Decentralized Identifier (DID) to a URN in the `did` namespace.
+    ///
+    /// `did:method:specific-id` becomes `urn:did:method:specific-id`, keeping the method
+    /// and the method-specific id, colons included, as the NSS. A DID already written as
+    /// `urn:did:...` is accepted too. DID URLs, with a path, query or fragment, aren't.
+    ///
+    /// # Parameters
+    ///
+    /// * `did` - The DID to map, e.g. `did:example:123456789abcdefghi`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Urn)` - The URN representing the DID.
+    /// * `Err(UrnError::Validation)` - If `did` isn't a valid DID.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    ///
+    /// let urn = Urn::from_did("did:web:example.com:user:alice").unwrap();
+    /// assert_eq!(urn.to_string(), "urn:did:web:example.com:user:alice");
+    /// assert_eq!(urn.as_did().as_deref(), Some("did:web:example.com:user:alice"));
+    /// ```
+    pub fn from_did(did: &str) -> Result<Urn, UrnError> {
+        let body = did.strip_prefix("urn:")
+            .unwrap_or(did)
+            .strip_prefix("did:")
+            .ok_or_else(|| UrnError::Validation(format!("`{did}` is not a DID")))?;
+        if !is_valid_did_body(body) {
+            return Err(UrnError::Validation(format!("`{did}` is not a DID")));
+        }
+        Ok(Urn {
+            nid: Arc::from("did"),
+            nss: Arc::from(body),
+            path: None,
+            query: None,
+            fragment: None,
+            raw: None,
+            builder_settings: BuilderOnly,
+        })
+    }
+
+    /// Returns the DID this URN represents, if it's in the `did` namespace.
+    ///
+    /// This is the reverse of [`Urn::from_did`]. The path, query and fragment aren't part
+    /// of the DID and are ignored.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(String)` - The DID, e.g. `did:example:123456789abcdefghi`.
+    /// * `None` - If the NID isn't `did`, or the NSS isn't a valid DID method and id.
+    pub fn as_did(&self) -> Option<String> {
+        if !self.nid.eq_ignore_ascii_case("did") || !is_valid_did_body(&self.nss) {
+            return None;
+        }
+        Some(format!("did:{}", self.nss))
+    }
+
+    /// Creates the URN of a Cutoff project, `urn:cutoff:project/<id>`.
+    ///
+    /// # Parameters
+    ///
+    /// * `id` - The id of the project. It must be a non-empty string of ASCII letters,
+    ///   digits and `-`, such as a UUID, so it's stored unencoded and read back as is.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Urn)` - The URN of the project.
+    /// * `Err(UrnError::Validation)` - If `id` isn't a valid id.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    ///
+    /// let urn = Urn::cutoff_project("3f2a-91c0").unwrap();
+    /// assert_eq!(urn.to_string(), "urn:cutoff:project/3f2a-91c0");
+    /// assert_eq!(urn.as_cutoff_project(), Some("3f2a-91c0"));
+    /// ```
+    pub fn cutoff_project(id: &str) -> Result<Urn, UrnError> {
+        Self::cutoff_resource(CUTOFF_PROJECT, id)
+    }
+
+    /// Creates the URN of a Cutoff track, `urn:cutoff:track/<id>`.
+    ///
+    /// # Parameters
+    ///
+    /// * `id` - The id of the track, following the rules of [`Urn::cutoff_project`].
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Urn)` - The URN of the track.
+    /// * `Err(UrnError::Validation)` - If `id` isn't a valid id.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    ///
+    /// let urn = Urn::cutoff_track("42").unwrap();
+    /// assert_eq!(urn.to_string(), "urn:cutoff:track/42");
+    /// assert_eq!(urn.as_cutoff_track(), Some("42"));
+    /// assert_eq!(urn.as_cutoff_project(), None);
+    /// ```
+    pub fn cutoff_track(id: &str) -> Result<Urn, UrnError> {
+        Self::cutoff_resource(CUTOFF_TRACK, id)
+    }
+
+    /// Returns the id of the Cutoff project this URN identifies.
+    ///
+    /// This is the reverse of [`Urn::cutoff_project`]. The query and fragment are ignored.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(&str)` - The id of the project.
+    /// * `None` - If the URN isn't a `urn:cutoff:project/<id>` with a valid id.
+    pub fn as_cutoff_project(&self) -> Option<&str> {
+        self.as_cutoff_resource(CUTOFF_PROJECT)
+    }
+
+    /// Returns the id of the Cutoff track this URN identifies.
+    ///
+    /// This is the reverse of [`Urn::cutoff_track`]. The query and fragment are ignored.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(&str)` - The id of the track.
+    /// * `None` - If the URN isn't a `urn:cutoff:track/<id>` with a valid id.
+    pub fn as_cutoff_track(&self) -> Option<&str> {
+        self.as_cutoff_resource(CUTOFF_TRACK)
+    }
+
+    /// Creates the URN `urn:cutoff:<kind>/<id>`, checking the id.
+    fn cutoff_resource(kind: &str, id: &str) -> Result<Urn, UrnError> {
+        if !is_valid_cutoff_id(id) {
+            return Err(UrnError::Validation(format!("`{id}` is not a valid Cutoff {kind} id")));
+        }
+        Ok(Urn {
+            nid: Arc::from(CUTOFF_NID),
+            nss: Arc::from(kind),
+            path: Some(Arc::from(id)),
+            query: None,
+            fragment: None,
+            raw: None,
+            builder_settings: BuilderOnly,
+        })
+    }
+
+    /// Returns the id of a `urn:cutoff:<kind>/<id>` URN.
+    fn as_cutoff_resource(&self, kind: &str) -> Option<&str> {
+        if !self.nid.eq_ignore_ascii_case(CUTOFF_NID) || *self.nss != *kind {
+            return None;
+        }
+        self.path.as_deref().filter(|id| is_valid_cutoff_id(id))
+    }
+
+    /// Splits the NSS of a `urn:oid:` URN into its numeric arcs.
+    ///
+    /// # Returns
+    ///
+    /// * `None` - If the NID isn't `oid`.
+    /// * `Some(Ok(Vec<u64>))` - The arcs of the OID, e.g. `[1, 3, 6, 1]` for `1.3.6.1`.
+    /// * `Some(Err(UrnError::Validation))` - If an arc is empty, isn't a decimal number,
+    ///   has a leading zero, or doesn't fit in a `u64`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let urn = Urn::from_str("urn:oid:1.3.6.1.4.1").unwrap();
+    /// assert_eq!(urn.oid_arcs().unwrap().unwrap(), [1, 3, 6, 1, 4, 1]);
+    ///
+    /// assert!(Urn::from_str("urn:example:1.3").unwrap().oid_arcs().is_none());
+    /// ```
+    pub fn oid_arcs(&self) -> Option<Result<Vec<u64>, UrnError>> {
+        if !self.nid.eq_ignore_ascii_case("oid") {
+            return None;
+        }
+        let arcs = OidValidator.validate_nss(&self.nss)
+            .and_then(|()| {
+                self.nss.split('.')
+                    .map(|arc| arc.parse::<u64>().map_err(|_| format!("OID arc `{arc}` is too large")))
+                    .collect()
+            })
+            .map_err(UrnError::Validation);
+        Some(arcs)
+    }
+
+    /// Checks if the URN is a relative reference, i.e. if it has no NSS.
+    ///
+    /// A relative URN only carries a path, query and/or fragment, and must be
+    /// [resolved](Urn::resolve) against an absolute base URN to identify a resource.
+    /// Parsing always produces absolute URNs: relative ones can only be assembled with
+    /// the builder in lenient mode, leaving the NID and NSS empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let relative = Urn::builder().nid("").nss("").path("child").strict(false).build().unwrap();
+    /// assert!(relative.is_relative());
+    /// assert!(Urn::from_str("urn:example:resource").unwrap().is_absolute());
+    /// ```
+    pub fn is_relative(&self) -> bool {
+        self.nss.is_empty()
+    }
+
+    /// Checks if the URN is absolute, i.e. if it has an NSS. See [`Urn::is_relative`].
+    pub fn is_absolute(&self) -> bool {
+        !self.is_relative()
+    }
+
+    /// Resolves this URN against an absolute `base`.
+    ///
+    /// A relative URN takes the NID and NSS of `base`, along with its path when it has
+    /// none of its own; its query and fragment are kept as they are. An absolute URN is
+    /// returned unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let base = Urn::from_str("urn:example:resource/path").unwrap();
+    /// let relative = Urn::builder().nid("").nss("").fragment("section").strict(false).build().unwrap();
+    /// assert_eq!(relative.resolve(&base).to_string(), "urn:example:resource/path#section");
+    /// ```
+    pub fn resolve(&self, base: &Urn) -> Urn {
+        if self.is_absolute() {
+            return self.clone();
+        }
+        Urn {
+            nid: base.nid.clone(),
+            nss: base.nss.clone(),
+            path: self.path.clone().or_else(|| base.path.clone()),
+            query: self.query.clone(),
+            fragment: self.fragment.clone(),
+            raw: None,
+            builder_settings: BuilderOnly,
+        }
+    }
+
+    /// Converts the URN to a URL, if possible.
+    pub fn to_url(&self) -> Option<Url> {
+        Url::parse(&self.to_string()).ok()
+    }
+
+    /// Maps the URN to a URL under the given base, typically an HTTP gateway serving the
+    /// resources the URNs identify.
+    ///
+    /// The URL path is the base path followed by these segments:
+    /// - the NID, in lowercase
+    /// - the NSS
+    /// - each non-empty segment of the path, if any
+    ///
+    /// The NSS and segments are percent-decoded, then encoded for a URL path segment, so
+    /// an encoded `/` stays within its segment. `.` and `..` segments are dropped, so the
+    /// result always stays under the base. The base's query and fragment are replaced
+    /// with the URN's.
+    ///
+    /// # Parameters
+    ///
+    /// * `base` - The URL the URN is mapped under. A trailing `/` is optional.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Url)` - The URL `<base>/<nid>/<nss>[/<segment>...][?<query>][#<fragment>]`.
+    /// * `None` - If `base` can't be a base, like `mailto:` or `data:` URLs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    /// use url::Url;
+    ///
+    /// let base = Url::parse("https://gw.example/urn/").unwrap();
+    /// let urn = Urn::from_str("urn:example:doc/1").unwrap();
+    /// assert_eq!(urn.resolve_via(&base).unwrap().as_str(), "https://gw.example/urn/example/doc/1");
+    ///
+    /// let mailto = Url::parse("mailto:gw@example.com").unwrap();
+    /// assert_eq!(urn.resolve_via(&mailto), None);
+    /// ```
+    pub fn resolve_via(&self, base: &Url) -> Option<Url> {
+        let mut url = base.clone();
+        {
+            let mut segments = url.path_segments_mut().ok()?;
+            segments.pop_if_empty()
+                .push(&self.nid.to_lowercase())
+                .push(&self.decoded_nss());
+            if let Some(path_segments) = self.path_segments_decoded() {
+                segments.extend(path_segments.filter(|segment| !segment.is_empty()));
+            }
+        }
+        url.set_query(self.query.as_deref());
+        url.set_fragment(self.fragment.as_deref());
+        Some(url)
+    }
+
+    /// Compares two URNs for equality, ignoring case sensitivity in the scheme and namespace identifier.
+    pub fn equals(&self, other: &Self) -> bool {
+        self.nid_equals(other) &&
+            self.nss == other.nss &&
+            self.path == other.path &&
+            self.query == other.query &&
+            self.fragment == other.fragment
+    }
+
+    /// Compares the NIDs case-insensitively, the way `equals` and `Hash` do. A lenient
+    /// builder accepts non-ASCII NIDs, so the comparison isn't limited to ASCII.
+    fn nid_equals(&self, other: &Self) -> bool {
+        self.nid.to_lowercase() == other.nid.to_lowercase()
+    }
+
+    /// Normalizes the URN by converting the scheme and namespace identifier to lowercase.
+    pub fn normalize(&self) -> Self {
+        Urn {
+            nid: self.nid.to_lowercase().into(),
+            nss: self.nss.clone(),
+            path: self.path.clone(),
+            query: self.query.clone(),
+            fragment: self.fragment.clone(),
+            raw: None,
+            builder_settings: BuilderOnly,
+        }
+    }
+
+    /// Normalizes the percent-encoding of the URN by uppercasing the hexadecimal digits of
+    /// every `%XX` triplet, as RFC 8141 recommends.
+    ///
+    /// Every other character keeps its case, since it may be significant outside the NID.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let urn = Urn::from_str("urn:example:caf%c3%a9/a%2fB?q=%3d").unwrap();
+    /// assert_eq!(urn.normalize_percent_encoding().to_string(), "urn:example:caf%C3%A9/a%2FB?q=%3D");
+    /// ```
+    pub fn normalize_percent_encoding(&self) -> Self {
+        let normalize = |value: &Arc<str>| match uppercase_percent_encoding(value) {
+            Cow::Borrowed(_) => value.clone(),
+            Cow::Owned(normalized) => Arc::from(normalized),
+        };
+        Urn {
+            nid: normalize(&self.nid),
+            nss: normalize(&self.nss),
+            path: self.path.as_ref().map(normalize),
+            query: self.query.as_ref().map(normalize),
+            fragment: self.fragment.as_ref().map(normalize),
+            raw: None,
+            builder_settings: BuilderOnly,
+        }
+    }
+
+    /// Creates a new URN with the given query string.
+    ///
+    /// Characters that aren't allowed in a query are percent-encoded, while existing
+    /// `%XX` sequences are kept as they are.
+    pub fn with_query(&self, query: Option<&str>) -> Self {
+        Urn {
+            nid: self.nid.clone(),
+            nss: self.nss.clone(),
+            path: self.path.clone(),
+            query: query.map(|q| Arc::from(encode_component(q, is_fragment_byte))),
+            fragment: self.fragment.clone(),
+            raw: None,
+            builder_settings: BuilderOnly,
+        }
+    }
+
+    /// Creates a new URN with the given fragment.
+    ///
+    /// Characters that aren't allowed in an RFC 8141 f-component are percent-encoded,
+    /// while existing `%XX` sequences are kept as they are.
+    pub fn with_fragment(&self, fragment: Option<&str>) -> Self {
+        Urn {
+            nid: self.nid.clone(),
+            nss: self.nss.clone(),
+            path: self.path.clone(),
+            query: self.query.clone(),
+            fragment: fragment.map(|f| Arc::from(encode_component(f, is_fragment_byte))),
+            raw: None,
+            builder_settings: BuilderOnly,
+        }
+    }
+
+    /// Creates a new URN without the query component.
+    pub fn without_query(&self) -> Self {
+        self.with_query(None)
+    }
+
+
+    /// Creates a new URN without the fragment component.
+    pub fn without_fragment(&self) -> Self {
+        self.with_fragment(None)
+    }
+
+    /// Parses the query string into its key-value pairs, in order and keeping duplicates.
+    ///
+    /// Keys and values are form-urlencoded-decoded, `+` included. A key without `=` gets
+    /// an empty value. Unlike [`Urn::parse_query`], nothing is lost, which matters for
+    /// instance when signing a query string.
+    ///
+    /// # Returns
+    ///
+    /// The pairs, in the order they appear. No query, or an empty one, gives no pair.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let urn = Urn::from_str("urn:example:resource?b=2&a=1&b=3").unwrap();
+    /// let pairs: Vec<(String, String)> = urn.query_pairs();
+    /// assert_eq!(pairs[0], ("b".to_string(), "2".to_string()));
+    /// assert_eq!(pairs[2], ("b".to_string(), "3".to_string()));
+    /// ```
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        self.query.as_ref()
+            .map(|q| url::form_urlencoded::parse(q.as_bytes()).into_owned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Parses the query string into a key-value map.
+    ///
+    /// When a key appears several times, only its last value is kept: see
+    /// [`Urn::query_pairs`] to keep them all.
+    pub fn parse_query(&self) -> Option<std::collections::HashMap<String, String>> {
+        self.query.as_ref().map(|q| {
+            url::form_urlencoded::parse(q.as_bytes())
+                .into_owned()
+                .collect()
+        })
+    }
+
+    /// Parses the fragment into its key-value pairs, like media fragments such as `t=10,20`.
+    ///
+    /// The pairs are separated by `&` or `;`, and empty pieces are skipped. A piece without
+    /// `=` is a key with an empty value, like in [`Urn::query_pairs`], so a plain fragment
+    /// such as `section1` gives a single pair. Keys and values are percent-decoded, but
+    /// unlike in a query, `+` is kept as is.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Vec<(String, String)>)` - The pairs, in the order they appear.
+    /// * `None` - If the URN has no fragment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let urn = Urn::from_str("urn:example:video#t=10,20&xywh=160,120,320,240").unwrap();
+    /// let params = urn.fragment_params().unwrap();
+    /// assert_eq!(params[0], ("t".to_string(), "10,20".to_string()));
+    /// assert_eq!(params[1], ("xywh".to_string(), "160,120,320,240".to_string()));
+    /// ```
+    pub fn fragment_params(&self) -> Option<Vec<(String, String)>> {
+        let decode = |s: &str| percent_decode_str(s).decode_utf8_lossy().into_owned();
+        self.fragment.as_deref().map(|fragment| {
+            fragment.split(['&', ';'])
+                .filter(|piece| !piece.is_empty())
+                .map(|piece| {
+                    let (key, value) = piece.split_once('=').unwrap_or((piece, ""));
+                    (decode(key), decode(value))
+                })
+                .collect()
+        })
+    }
+
+    /// Creates a new URN whose query has the given defaults for the keys it lacks.
+    ///
+    /// The existing query is kept as it is, values and order included, and each default
+    /// whose key isn't in it is appended, form-urlencoded. When a key appears several
+    /// times in `defaults`, the first occurrence wins.
+    ///
+    /// # Parameters
+    ///
+    /// * `defaults` - The default key-value pairs, in the order to append them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let urn = Urn::from_str("urn:example:resource?format=json").unwrap();
+    /// let with_defaults = urn.with_query_defaults(&[("format", "xml"), ("lang", "en")]);
+    /// assert_eq!(with_defaults.to_string(), "urn:example:resource?format=json&lang=en");
+    /// ```
+    pub fn with_query_defaults(&self, defaults: &[(&str, &str)]) -> Self {
+        let mut keys: Vec<String> = self.query.as_deref()
+            .map(|q| url::form_urlencoded::parse(q.as_bytes()).map(|(k, _)| k.into_owned()).collect())
+            .unwrap_or_default();
+
+        let mut query = url::form_urlencoded::Serializer::new(
+            self.query.as_deref().unwrap_or_default().to_string()
+        );
+        let mut added = false;
+        for (key, value) in defaults {
+            if !keys.iter().any(|k| k == key) {
+                query.append_pair(key, value);
+                keys.push(key.to_string());
+                added = true;
+            }
+        }
+
+        if added {
+            self.with_query(Some(&query.finish()))
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Writes the string form of the URN into `w`, without allocating an intermediate `String`.
+    ///
+    /// This is what the `Display` implementation uses, and is handy to append many URNs
+    /// into a single buffer.
+    ///
+    /// Each component is percent-encoded according to the characters it allows, so that
+    /// the output parses back: spaces, delimiters such as `/`, `?` or `#` in the NSS, and
+    /// any other disallowed character become `%XX` sequences. Existing `%XX` sequences
+    /// are kept as they are, so already encoded components aren't encoded twice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let mut out = String::from("see ");
+    /// Urn::from_str("urn:example:resource").unwrap().write_to(&mut out).unwrap();
+    /// assert_eq!(out, "see urn:example:resource");
+    /// ```
+    pub fn write_to<W: Write>(&self, w: &mut W) -> std::fmt::Result {
+        // Characters such as `/`, `?` or `#` would be read back as delimiters
+        write!(w, "urn:{}:{}", self.nid, encode_component(&self.nss, is_nss_byte))?;
+        if let Some(path) = &self.path {
+            write!(w, "/{}", encode_component(path, is_path_byte))?;
+        }
+        if let Some(query) = &self.query {
+            // Queries allow the same characters as fragments (RFC 3986)
+            write!(w, "?{}", encode_component(query, is_fragment_byte))?;
+        }
+        if let Some(fragment) = &self.fragment {
+            write!(w, "#{}", encode_component(fragment, is_fragment_byte))?;
+        }
+        Ok(())
+    }
+
+    /// Checks if two URNs are lexically equivalent according to RFC 8141.
+    pub fn is_lexically_equivalent(&self, other: &Self) -> bool {
+        let norm_self = self.normalize();
+        let norm_other = other.normalize();
+
+        norm_self.nid == norm_other.nid &&
+            norm_self.nss == norm_other.nss &&
+            norm_self.path == norm_other.path
+        // Note: query and fragment are not considered for lexical equivalence
+    }
+
+    /// Lists the components that differ between this URN and another.
+    ///
+    /// Components are compared like `==` does: the NID case-insensitively, everything
+    /// else byte for byte. An empty list means the URNs are equal.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - The URN to compare with.
+    ///
+    /// # Returns
+    ///
+    /// The differing components, in the order they appear in a URN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::{Urn, UrnComponent};
+    /// use std::str::FromStr;
+    ///
+    /// let a = Urn::from_str("urn:example:resource?v=1").unwrap();
+    /// let b = Urn::from_str("urn:example:other?v=2").unwrap();
+    /// assert_eq!(a.component_diff(&b), [UrnComponent::Nss, UrnComponent::Query]);
+    /// ```
+    pub fn component_diff(&self, other: &Urn) -> Vec<UrnComponent> {
+        let differences = [
+            (UrnComponent::Nid, !self.nid_equals(other)),
+            (UrnComponent::Nss, self.nss != other.nss),
+            (UrnComponent::Path, self.path != other.path),
+            (UrnComponent::Query, self.query != other.query),
+            (UrnComponent::Fragment, self.fragment != other.fragment),
+        ];
+        differences.into_iter()
+            .filter(|(_, differs)| *differs)
+            .map(|(component, _)| component)
+            .collect()
+    }
+}
+
+impl PartialEq for Urn {
+    fn eq(&self, other: &Self) -> bool {
+        self.equals(other)
+    }
+}
+
+impl Eq for Urn {}
+
+impl Hash for Urn {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // The NID is hashed lowercased to stay consistent with `equals`
+        self.nid.to_lowercase().hash(state);
+        self.nss.hash(state);
+        self.path.hash(state);
+        self.query.hash(state);
+        self.fragment.hash(state);
+    }
+}
+
+/// A `Urn` wrapper that compares and hashes by lexical equivalence.
+///
+/// Two keys are equal when their URNs are [lexically equivalent](Urn::is_lexically_equivalent):
+/// the NID is compared case-insensitively and the query and fragment are ignored. This
+/// makes `UrnKey` suitable as a `HashMap` key identifying a resource regardless of how
+/// its URN was written.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::urn::{Urn, UrnKey};
+/// use std::collections::HashMap;
+/// use std::str::FromStr;
+///
+/// let mut map = HashMap::new();
+/// map.insert(UrnKey::from(Urn::from_str("urn:EXAMPLE:resource?v=1").unwrap()), 42);
+///
+/// let lookup = UrnKey::from(Urn::from_str("urn:example:resource").unwrap());
+/// assert_eq!(map.get(&lookup), Some(&42));
+/// ```
+#[derive(Debug, Clone)]
+pub struct UrnKey(Urn);
+
+impl UrnKey {
+    /// Returns the wrapped URN.
+    pub fn urn(&self) -> &Urn { &self.0 }
+
+    /// Consumes the key, returning the wrapped URN.
+    pub fn into_inner(self) -> Urn { self.0 }
+}
+
+impl From<Urn> for UrnKey {
+    fn from(urn: Urn) -> Self {
+        UrnKey(urn)
+    }
+}
+
+impl PartialEq for UrnKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.is_lexically_equivalent(&other.0)
+    }
+}
+
+impl Eq for UrnKey {}
+
+impl Hash for UrnKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Only the components taking part in lexical equivalence are hashed
+        self.0.nid.to_lowercase().hash(state);
+        self.0.nss.hash(state);
+        self.0.path.hash(state);
+    }
+}
+
+/// Names a component of a `Urn`, e.g. in the result of [`Urn::component_diff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UrnComponent {
+    Nid,
+    Nss,
+    Path,
+    Query,
+    Fragment,
+}
+
+impl Display for UrnComponent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            UrnComponent::Nid => "NID",
+            UrnComponent::Nss => "NSS",
+            UrnComponent::Path => "path",
+            UrnComponent::Query => "query",
+            UrnComponent::Fragment => "fragment",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A reusable notion of `Urn` ordering, for sorting and deduplicating collections.
+///
+/// Each variant defines a total order together with its matching equality, so a single
+/// value can be passed to `sort_by` and `dedup_by` instead of a pair of closures.
+///
+/// * `Strict` - Compares every component exactly, including the case of the NID.
+/// * `Lexical` - Follows [lexical equivalence](Urn::is_lexically_equivalent): the NID is
+///   compared case-insensitively and the query and fragment are ignored.
+/// * `CaseInsensitive` - Compares every component, ignoring case everywhere.
+///
+/// Note that `Strict` is stricter than `==` on `Urn`, which ignores the case of the NID.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::urn::{Urn, UrnComparator};
+/// use std::str::FromStr;
+///
+/// let mut urns: Vec<Urn> = ["urn:example:b", "urn:EXAMPLE:a", "urn:example:a?v=2"]
+///     .iter()
+///     .map(|s| Urn::from_str(s).unwrap())
+///     .collect();
+///
+/// let comparator = UrnComparator::Lexical;
+/// urns.sort_by(|a, b| comparator.cmp(a, b));
+/// urns.dedup_by(|a, b| comparator.eq(a, b));
+///
+/// assert_eq!(urns.len(), 2);
+/// assert_eq!(urns[1].nss(), "b");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrnComparator {
+    Strict,
+    Lexical,
+    CaseInsensitive,
+}
+
+impl UrnComparator {
+    /// Compares two URNs according to this comparator.
+    ///
+    /// Components are compared in order: NID, NSS, path, query and fragment, with a
+    /// missing component ordered before a present one.
+    ///
+    /// # Parameters
+    ///
+    /// * `a` - The first URN.
+    /// * `b` - The second URN.
+    ///
+    /// # Returns
+    ///
+    /// The ordering of `a` relative to `b`.
+    pub fn cmp(&self, a: &Urn, b: &Urn) -> Ordering {
+        match self {
+            UrnComparator::Strict => a.nid.cmp(&b.nid)
+                .then_with(|| a.nss.cmp(&b.nss))
+                .then_with(|| a.path.cmp(&b.path))
+                .then_with(|| a.query.cmp(&b.query))
+                .then_with(|| a.fragment.cmp(&b.fragment)),
+            UrnComparator::Lexical => cmp_ignore_case(&a.nid, &b.nid)
+                .then_with(|| a.nss.cmp(&b.nss))
+                .then_with(|| a.path.cmp(&b.path)),
+            UrnComparator::CaseInsensitive => cmp_ignore_case(&a.nid, &b.nid)
+                .then_with(|| cmp_ignore_case(&a.nss, &b.nss))
+                .then_with(|| cmp_opt_ignore_case(&a.path, &b.path))
+                .then_with(|| cmp_opt_ignore_case(&a.query, &b.query))
+                .then_with(|| cmp_opt_ignore_case(&a.fragment, &b.fragment)),
+        }
+    }
+
+    /// Checks whether two URNs are equal according to this comparator.
+    ///
+    /// This is always consistent with [`cmp`](UrnComparator::cmp) returning `Ordering::Equal`.
+    pub fn eq(&self, a: &Urn, b: &Urn) -> bool {
+        self.cmp(a, b) == Ordering::Equal
+    }
+}
+
+/// Compares two strings, ignoring case.
+fn cmp_ignore_case(a: &str, b: &str) -> Ordering {
+    a.chars().flat_map(char::to_lowercase).cmp(b.chars().flat_map(char::to_lowercase))
+}
+
+/// Compares two optional strings, ignoring case. `None` is ordered first.
+fn cmp_opt_ignore_case(a: &Option<Arc<str>>, b: &Option<Arc<str>>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => cmp_ignore_case(a, b),
+        _ => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+/// Validates the NSS of the URNs of a given namespace.
+///
+/// Each namespace has its own NSS syntax (a UUID, an OID, an ISBN...). Validators are
+/// registered per NID in a [`NidValidatorRegistry`], and applied with
+/// [`Urn::validate_against`].
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::urn::{NidValidator, NidValidatorRegistry, Urn};
+/// use std::str::FromStr;
+///
+/// struct Isbn;
+///
+/// impl NidValidator for Isbn {
+///     fn validate_nss(&self, nss: &str) -> Result<(), String> {
+///         let digits = nss.chars().filter(char::is_ascii_digit).count();
+///         if digits == 10 || digits == 13 {
+///             Ok(())
+///         } else {
+///             Err(format!("`{nss}` is not an ISBN"))
+///         }
+///     }
+/// }
+///
+/// let mut validators = NidValidatorRegistry::new();
+/// validators.register("isbn", Isbn);
+///
+/// let urn = Urn::from_str("urn:isbn:0451450523").unwrap();
+/// assert!(urn.validate_against(&validators).is_ok());
+/// ```
+pub trait NidValidator: Send + Sync {
+    /// Checks whether `nss` is a valid NSS for this validator's namespace.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the NSS is valid.
+    /// * `Err(String)` - A description of why the NSS is invalid.
+    fn validate_nss(&self, nss: &str) -> Result<(), String>;
+}
+
+/// A set of [`NidValidator`]s, keyed by the NID they apply to.
+///
+/// NIDs are matched case-insensitively, like everywhere else in URN comparisons.
+#[derive(Default)]
+pub struct NidValidatorRegistry {
+    /// The validators, keyed by lowercased NID
+    validators: HashMap<String, Box<dyn NidValidator>>,
+}
+
+impl NidValidatorRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry with the built-in validators for the `uuid` and `oid` namespaces.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register("uuid", UuidValidator);
+        registry.register("oid", OidValidator);
+        registry
+    }
+
+    /// Registers `validator` for `nid`, replacing any validator previously registered for it.
+    ///
+    /// # Parameters
+    ///
+    /// * `nid` - The NID the validator applies to.
+    /// * `validator` - The validator.
+    pub fn register<V: NidValidator + 'static>(&mut self, nid: &str, validator: V) {
+        self.validators.insert(nid.to_lowercase(), Box::new(validator));
+    }
+
+    /// Returns the validator registered for `nid`, if any.
+    pub fn get(&self, nid: &str) -> Option<&dyn NidValidator> {
+        self.validators.get(&nid.to_lowercase()).map(|v| v.as_ref())
+    }
+}
+
+impl std::fmt::Debug for NidValidatorRegistry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.validators.keys()).finish()
+    }
+}
+
+/// Validates the NSS of `urn:uuid:` URNs (RFC 4122): a UUID in its hyphenated
+/// hexadecimal form, such as `f81d4fae-7dec-11d0-a765-00a0c91e6bf6`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidValidator;
+
+impl NidValidator for UuidValidator {
+    fn validate_nss(&self, nss: &str) -> Result<(), String> {
+        let valid = nss.len() == 36 && nss.bytes().enumerate().all(|(i, b)| match i {
+            8 | 13 | 18 | 23 => b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        });
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("`{nss}` is not a UUID"))
+        }
+    }
+}
+
+/// Validates the NSS of `urn:oid:` URNs (RFC 3061): dot-separated decimal arcs without
+/// leading zeros, such as `1.3.6.1`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OidValidator;
+
+impl NidValidator for OidValidator {
+    fn validate_nss(&self, nss: &str) -> Result<(), String> {
+        let valid = nss.split('.').all(|arc| {
+            !arc.is_empty()
+                && arc.bytes().all(|b| b.is_ascii_digit())
+                && (arc == "0" || !arc.starts_with('0'))
+        });
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("`{nss}` is not an OID"))
+        }
+    }
+}
+
+impl UrnBuilder {
+    /// Sets the r-component (RFC 8141), keeping any q-component already set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    ///
+    /// let urn = Urn::builder()
+    ///     .nid("example")
+    ///     .nss("foo")
+    ///     .qcomponent("k=v")
+    ///     .rcomponent("res")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(urn.to_string(), "urn:example:foo?+res?=k=v");
+    /// ```
+    pub fn rcomponent(&mut self, rcomponent: &str) -> &mut Self {
+        let qcomponent = self.built_rq_components().1;
+        self.set_rq_components(Some(rcomponent), qcomponent.as_deref())
+    }
+
+    /// Sets the q-component (RFC 8141), keeping any r-component already set.
+    pub fn qcomponent(&mut self, qcomponent: &str) -> &mut Self {
+        let rcomponent = self.built_rq_components().0;
+        self.set_rq_components(rcomponent.as_deref(), Some(qcomponent))
+    }
+
+    /// Returns the r-component and q-component currently set.
+    fn built_rq_components(&self) -> (Option<String>, Option<String>) {
+        match self.query.as_ref().and_then(|q| q.as_deref()) {
+            Some(query) => {
+                let (r, q) = split_rq_components(query);
+                (r.map(String::from), q.map(String::from))
+            }
+            None => (None, None),
+        }
+    }
+
+    /// Sets the query from an r-component and a q-component.
+    fn set_rq_components(&mut self, rcomponent: Option<&str>, qcomponent: Option<&str>) -> &mut Self {
+        let mut query = String::new();
+        if let Some(r) = rcomponent {
+            query.push('+');
+            query.push_str(r);
+        }
+        if let Some(q) = qcomponent {
+            // The leading `?` is the one written before the whole query
+            if rcomponent.is_some() {
+                query.push('?');
+            }
+            query.push('=');
+            query.push_str(q);
+        }
+        self.query(query)
+    }
+
+    /// Sets whether `build` validates the URN.
+    ///
+    /// In strict mode, which is the default, `build` fails with a validation error on a
+    /// URN that isn't [valid](Urn::is_valid), or whose NID doesn't follow RFC 8141: 2 to
+    /// 32 letters, digits or hyphens, not starting or ending with a hyphen. In lenient
+    /// mode, `build` accepts any components, as long as the NID and NSS are set.
+    ///
+    /// # Parameters
+    ///
+    /// * `strict` - `true` to validate the URN, `false` to build anything.
+    pub fn strict(&mut self, strict: bool) -> &mut Self {
+        self.builder_settings.strict = strict;
+        self
+    }
+
+    /// Sets the maximum depth of the path, in segments, that `build` accepts.
+    ///
+    /// The depth counts the non-empty segments of the path, like [`Urn::path_segments`],
+    /// and a URN without a path has a depth of zero. A deeper path makes `build` fail
+    /// with a validation error, and `try_build` with [`UrnError::PathTooDeep`], whether
+    /// the builder is strict or not. This catches a runaway recursion building ever
+    /// deeper URNs.
+    ///
+    /// # Parameters
+    ///
+    /// * `max` - The maximum number of path segments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::{Urn, UrnError};
+    ///
+    /// let mut builder = Urn::builder();
+    /// builder.nid("example").nss("resource").max_path_depth(2);
+    ///
+    /// assert!(builder.path("a/b").try_build().is_ok());
+    /// assert!(matches!(
+    ///     builder.path("a/b/c").try_build(),
+    ///     Err(UrnError::PathTooDeep { depth: 3, max: 2 })
+    /// ));
+    /// ```
+    pub fn max_path_depth(&mut self, max: usize) -> &mut Self {
+        self.builder_settings.max_path_depth = Some(max);
+        self
+    }
+
+    /// Returns the depth of the path and the maximum depth, when the path is too deep.
+    fn path_too_deep(&self) -> Option<(usize, usize)> {
+        let max = self.builder_settings.max_path_depth?;
+        let depth = self.path.as_ref()?.as_deref()?.split('/').filter(|s| !s.is_empty()).count();
+        (depth > max).then_some((depth, max))
+    }
+
+    /// The validation run by `build`, depending on the strict mode.
+    fn validate(&self) -> Result<(), String> {
+        if let Some((depth, max)) = self.path_too_deep() {
+            return Err(format!("path depth {depth} exceeds the maximum of {max}"));
+        }
+        if !self.builder_settings.strict {
+            return Ok(());
+        }
+        let is_empty = |component: &Option<Arc<str>>| component.as_ref().is_some_and(|c| c.is_empty());
+        if is_empty(&self.nid) || is_empty(&self.nss) {
+            return Err("NID and NSS must not be empty".to_string());
+        }
+        if let Some(nid) = self.nid.as_deref() {
+            if let Some(position) = find_invalid_nid_byte(nid) {
+                return Err(format!(
+                    "invalid NID `{nid}` at position {position}: an NID is 2 to 32 letters, digits \
+                     or hyphens, not starting or ending with a hyphen"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the URN, validating it in strict mode.
+    ///
+    /// The NSS, path, query and fragment are percent-encoded like [`Urn::write_to`] does,
+    /// so the built URN holds the same components as the URN parsed from its string
+    /// form, and both compare equal.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Urn)` - The built URN.
+    /// * `Err(UrnBuilderError)` - If the NID or NSS is missing, or the URN doesn't pass
+    ///   validation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cutoff_common::urn::Urn;
+    /// use std::str::FromStr;
+    ///
+    /// let built = Urn::builder().nid("example").nss("foo bar/baz").build().unwrap();
+    /// assert_eq!(built.nss(), "foo%20bar%2Fbaz");
+    /// assert_eq!(Urn::from_str(&built.to_string()).unwrap(), built);
+    /// ```
+    pub fn build(&self) -> Result<Urn, UrnBuilderError> {
+        let urn = self.build_unencoded()?;
+        let encode = |component: &Arc<str>, allowed| match encode_component(component, allowed) {
+            Cow::Borrowed(_) => component.clone(),
+            Cow::Owned(encoded) => Arc::from(encoded),
+        };
+        Ok(Urn {
+            nss: encode(&urn.nss, is_nss_byte),
+            path: urn.path.as_ref().map(|path| encode(path, is_path_byte)),
+            // Queries allow the same characters as fragments (RFC 3986)
+            query: urn.query.as_ref().map(|query| encode(query, is_fragment_byte)),
+            fragment: urn.fragment.as_ref().map(|fragment| encode(fragment, is_fragment_byte)),
+            ..urn
+        })
+    }
+
+    /// Builds the URN and validates it, reporting failures as a [`UrnError`].
+    ///
+    /// Unlike the generated `build`, this returns the same error type as the
+    /// rest of the module, so construction and parsing can share `?` chains.
+    pub fn try_build(&self) -> Result<Urn, UrnError> {
+        if let Some((depth, max)) = self.path_too_deep() {
+            return Err(UrnError::PathTooDeep { depth, max });
+        }
+        let urn = self.build()?;
+        if !urn.is_valid() {
+            return Err(UrnError::Validation("NID and NSS must not be empty".to_string()));
+        }
+        Ok(urn)
+    }
+}
+
+impl FromStr for Urn {
+    type Err = UrnFormatError;
+
+    fn from_str(urn_string: &str) -> Result<Self, Self::Err> {
+        // Check if the string starts with "urn:", the scheme being case-insensitive (RFC 8141).
+        // The URL parser lowercases it, and `Display` always writes it in lowercase.
+        if !urn_string.get(..4).is_some_and(|scheme| scheme.eq_ignore_ascii_case("urn:")) {
+            return Err(UrnFormatError::UrnSchemeExpected);
+        }
+
+        // The fragment is checked on the raw input, before the URL parser encodes it
+        if let Some(start) = urn_string.find('#').map(|i| i + 1) {
+            if let Some(offset) = find_invalid_byte(&urn_string[start..], is_fragment_byte) {
+                return Err(UrnFormatError::InvalidFragment { position: start + offset });
+            }
+        }
+
+        let url = Url::parse(urn_string)
+            .map_err(|_| UrnFormatError::InvalidUrn)?;
+
+        if !is_valid_percent_encoding(url.path()) {
+            return Err(UrnFormatError::InvalidPercentEncoding);
+        }
+
+        if let Some(captures) = URN_PATTERN.captures(url.path()) {
+            let urn = Urn {
+                nid: captures.get(1).map_or("", |m| m.as_str()).into(),
+                nss: captures.get(2).map_or("", |m| m.as_str()).into(),
+                path: captures.get(3).map(|m| m.as_str().into()),
+                // The URL parser leaves some characters as they are, like `[` or `|`, that
+                // `Display` encodes: the query is stored encoded, so that it round-trips
+                query: url.query()
+                    .map(|query| Arc::from(encode_component(query, is_fragment_byte))),
+                fragment: url.fragment().map(Arc::from),
+                raw: None,
+                builder_settings: BuilderOnly,
+            };
+            Ok(urn)
+        } else {
+            Err(UrnFormatError::InvalidUrn)
+        }
+    }
+}
+
+impl TryFrom<&str> for Urn {
+    type Error = UrnFormatError;
+
+    /// Parses a URN, like `from_str`.
+    fn try_from(urn_string: &str) -> Result<Self, Self::Error> {
+        Urn::from_str(urn_string)
+    }
+}
+
+impl TryFrom<String> for Urn {
+    type Error = UrnFormatError;
+
+    /// Parses a URN, like `from_str`. The string is only borrowed while parsing, as the
+    /// components are copied into their own shared buffers anyway.
+    fn try_from(urn_string: String) -> Result<Self, Self::Error> {
+        Urn::from_str(&urn_string)
+    }
+}
+
+impl Display for Urn {
+    /// Formats the URN as a string.
+    ///
+    /// # Returns
+    ///
+    /// A string representation of the URN in the format:
+    /// urn:<nid>:<nss>[/<path>][?<query>][#<fragment>]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.write_to(f)
+    }
+}
+
+/// Checks that every `%` in `value` starts a complete `%XX` hexadecimal triplet.
+fn is_valid_percent_encoding(value: &str) -> bool {
+    find_invalid_byte(value, |_| true).is_none()
+}
+
+/// Uppercases the hexadecimal digits of every `%XX` triplet in `value`, leaving anything
+/// else, including incomplete triplets, untouched.
+fn uppercase_percent_encoding(value: &str) -> Cow<'_, str> {
+    let bytes = value.as_bytes();
+    let is_lowercase_triplet = |i: usize| {
+        bytes[i] == b'%'
+            && bytes.get(i + 1..i + 3).is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            && bytes[i + 1..i + 3].iter().any(u8::is_ascii_lowercase)
+    };
+    if !(0..bytes.len()).any(is_lowercase_triplet) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut normalized = bytes.to_vec();
+    for i in 0..bytes.len() {
+        if is_lowercase_triplet(i) {
+            normalized[i + 1..i + 3].make_ascii_uppercase();
+        }
+    }
+    // Only ASCII hex digits changed, so the bytes are still valid UTF-8
+    Cow::Owned(String::from_utf8(normalized).expect("ASCII case change keeps UTF-8 valid"))
+}
+
+/// Checks whether `id` is a valid Cutoff resource id: a non-empty string of characters
+/// allowed unencoded in a path segment.
+fn is_valid_cutoff_id(id: &str) -> bool {
+    !id.is_empty() && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
+
+/// Checks whether `body` is a valid DID without its `did:` prefix (W3C DID Core): a
+/// lowercase alphanumeric method, a colon, then a method-specific id that may itself
+/// contain colons, but not end with one.
+fn is_valid_did_body(body: &str) -> bool {
+    let Some((method, id)) = body.split_once(':') else {
+        return false;
+    };
+    let is_id_byte = |b: u8| b.is_ascii_alphanumeric() || b".-_:".contains(&b);
+    !method.is_empty()
+        && method.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        && !id.is_empty()
+        && !id.ends_with(':')
+        && find_invalid_byte(id, is_id_byte).is_none()
+}
+
+/// Splits the text after `?` into its RFC 8141 r-component (`+...`) and q-component
+/// (`=...`, or `?=...` after an r-component). A plain query has neither.
+fn split_rq_components(query: &str) -> (Option<&str>, Option<&str>) {
+    if let Some(rest) = query.strip_prefix('+') {
+        match rest.split_once("?=") {
+            Some((r, q)) => (Some(r), Some(q)),
+            None => (Some(rest), None),
+        }
+    } else {
+        (None, query.strip_prefix('='))
+    }
+}
+
+/// Returns the byte offset of the first character making `nid` an invalid NID (RFC 8141):
+/// 2 to 32 letters, digits or hyphens, not starting or ending with a hyphen. The offset
+/// is the length of the NID when it's too short or too long.
+fn find_invalid_nid_byte(nid: &str) -> Option<usize> {
+    let bytes = nid.as_bytes();
+    let last = bytes.len().saturating_sub(1);
+    let invalid = bytes.iter().enumerate().position(|(i, &b)| {
+        !(b.is_ascii_alphanumeric() || (b == b'-' && i != 0 && i != last))
+    });
+    invalid.or_else(|| (!(2..=32).contains(&bytes.len())).then_some(bytes.len()))
+}
+
+/// Checks whether `byte` may appear unencoded in a path, as accepted by `URN_PATTERN`.
+fn is_path_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"-/".contains(&byte)
+}
+
+/// Checks whether `byte` may appear unencoded in an NSS. Colons are allowed, as only
+/// the first one separates the NID from the NSS.
+fn is_nss_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"-._:".contains(&byte)
+}
+
+/// Checks whether `byte` may appear unencoded in an f-component (RFC 8141 / RFC 3986):
+/// unreserved characters, sub-delims, `:`, `@`, `/` and `?`.
+fn is_fragment_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@/?".contains(&byte)
+}
+
+/// Returns the byte offset of the first character of `value` that is neither `allowed`
+/// nor part of a complete `%XX` triplet.
+fn find_invalid_byte(value: &str, allowed: fn(u8) -> bool) -> Option<usize> {
+    let bytes = value.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let triplet_ok = bytes.get(i + 1).is_some_and(u8::is_ascii_hexdigit)
+                && bytes.get(i + 2).is_some_and(u8::is_ascii_hexdigit);
+            if !triplet_ok {
+                return Some(i);
+            }
+            i += 3;
+        } else if allowed(bytes[i]) {
+            i += 1;
+        } else {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Percent-encodes every byte of `value` that isn't `allowed`.
+///
+/// Complete `%XX` triplets are kept as they are, so already-encoded input is not
+/// encoded twice.
+fn encode_component(value: &str, allowed: fn(u8) -> bool) -> Cow<'_, str> {
+    if find_invalid_byte(value, allowed).is_none() {
+        return Cow::Borrowed(value);
+    }
+
+    let bytes = value.as_bytes();
+    let mut encoded = String::with_capacity(value.len() + 8);
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        let is_triplet = byte == b'%'
+            && bytes.get(i + 1).is_some_and(u8::is_ascii_hexdigit)
+            && bytes.get(i + 2).is_some_and(u8::is_ascii_hexdigit);
+        if is_triplet {
+            encoded.push_str(&value[i..i + 3]);
+            i += 3;
+            continue;
+        }
+        if byte != b'%' && allowed(byte) {
+            encoded.push(byte as char);
+        } else {
+            let _ = write!(encoded, "%{:02X}", byte);
+        }
+        i += 1;
+    }
+    Cow::Owned(encoded)
+}
+
+#[derive(Error, Debug)]
+pub enum UrnFormatError {
+    /// Returned when the input string doesn't start with the "urn:" scheme.
+    #[error("Invalid URN: URN scheme expected, but not found")]
+    UrnSchemeExpected,
+
+    /// Returned when the input string doesn't match the expected URN format.
+    #[error("Invalid URN: unrecognizable URN format")]
+    InvalidUrn,
+
+    /// Returned when the input string contains a `%` that doesn't start a valid `%XX` sequence.
+    #[error("Invalid URN: malformed percent-encoding")]
+    InvalidPercentEncoding,
+
+    /// Returned when the fragment contains a character not allowed in an RFC 8141 f-component.
+    /// The position is the byte offset of the offending character in the input string.
+    #[error("Invalid URN: illegal character in fragment at position {position}")]
+    InvalidFragment { position: usize },
+}
+
+/// The unified error type for building and parsing URNs.
+#[derive(Error, Debug)]
+pub enum UrnError {
+    /// Returned when a URN string cannot be parsed.
+    #[error(transparent)]
+    Format(#[from] UrnFormatError),
+
+    /// Returned when a required component was not provided to the builder.
+    #[error("Invalid URN: missing component `{0}`")]
+    MissingComponent(&'static str),
+
+    /// Returned when a URN does not pass validation.
+    #[error("Invalid URN: {0}")]
+    Validation(String),
+
+    /// Returned by [`Urn::expand_template`] when a placeholder names an unknown variable.
+    #[error("Invalid URN template: missing variable `{0}`")]
+    MissingVariable(String),
+
+    /// Returned when decoding a URN from its binary encoding fails.
+    ///
+    /// The variant exists whether or not the `binary` feature is enabled, so enabling it
+    /// doesn't break exhaustive matches on `UrnError`.
+    #[error("Invalid URN encoding: {0}")]
+    InvalidEncoding(&'static str),
+
+    /// Returned by [`UrnBuilder::try_build`] when the path has more segments than
+    /// allowed by [`UrnBuilder::max_path_depth`].
+    #[error("Invalid URN: path depth {depth} exceeds the maximum of {max}")]
+    PathTooDeep {
+        /// The number of segments of the path
+        depth: usize,
+        /// The maximum number of segments
+        max: usize,
+    },
+
+    /// Returned when a component given to [`Urn::from_components`] is invalid.
+    #[error("Invalid URN: invalid {component} at position {position}")]
+    InvalidComponent {
+        /// The invalid component
+        component: UrnComponent,
+        /// The byte offset of the first invalid character within the component
+        position: usize,
+    },
+}
+
+/// A grammar violation reported by [`Urn::validate_all`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum UrnValidationError {
+    /// Returned when a required component is empty.
+    #[error("the {component} is empty")]
+    Empty {
+        /// The empty component
+        component: UrnComponent,
+    },
+
+    /// Returned when a component has a length outside of its allowed range, which only
+    /// applies to the NID (2 to 32 characters).
+    #[error("the {component} has an invalid length of {len}")]
+    InvalidLength {
+        /// The component with an invalid length
+        component: UrnComponent,
+        /// The length of the component, in bytes
+        len: usize,
+    },
+
+    /// Returned when a component contains a character it doesn't allow, including an
+    /// incomplete `%XX` sequence.
+    #[error("illegal character in the {component} at position {position}")]
+    IllegalCharacter {
+        /// The component containing the character
+        component: UrnComponent,
+        /// The byte offset of the character within the component
+        position: usize,
+    },
+}
+
+impl From<UrnBuilderError> for UrnError {
+    fn from(err: UrnBuilderError) -> Self {
+        match err {
+            UrnBuilderError::UninitializedField(field) => UrnError::MissingComponent(field),
+            UrnBuilderError::ValidationError(message) => UrnError::Validation(message),
+        }
+    }
+}
+
+/// Checks the round-trip invariant of URN parsing on `s`, for fuzzing and property tests.
+///
+/// The invariant is that a URN parsed from `s` serializes back into a string that parses
+/// again, into a URN equal to the first one, query and fragment included. Inputs that
+/// don't parse in the first place trivially hold.
+///
+/// This function is only available with the `testutil` feature.
+///
+/// # Returns
+///
+/// `true` if the invariant holds for `s`, `false` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use cutoff_common::urn::roundtrip_ok;
+///
+/// assert!(roundtrip_ok("urn:example:resource/path?k=v#section"));
+/// assert!(roundtrip_ok("not a urn"));
+/// ```
+#[cfg(feature = "testutil")]
+pub fn roundtrip_ok(s: &str) -> bool {
+    let Ok(urn) = Urn::from_str(s) else {
+        return true;
+    };
+    match Urn::from_str(&urn.to_string()) {
+        Ok(reparsed) => reparsed == urn,
+        Err(_) => false,
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde {
+    use crate::urn::Urn;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::str::FromStr;
+
+    impl Serialize for Urn {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_str(&self.to_string())
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Urn {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = String::deserialize(deserializer)?;
+            Urn::from_str(&s)
+                .map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+#[cfg(feature = "binary")]
+mod binary {
+    use crate::urn::{BuilderOnly, Urn, UrnError};
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    /// Flags telling which optional components follow the NID and NSS.
+    const HAS_PATH: u8 = 0b001;
+    const HAS_QUERY: u8 = 0b010;
+    const HAS_FRAGMENT: u8 = 0b100;
+
+    impl Urn {
+        /// Encodes the URN into a compact binary form.
+        ///
+        /// The encoding starts with a flags byte telling which optional components are
+        /// present, followed by the NID, the NSS and the present optional components, each
+        /// prefixed by its length as an unsigned LEB128 varint. It is meant for compact
+        /// storage, not for interoperability: use the string form or serde for that.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use cutoff_common::urn::Urn;
+        /// use std::str::FromStr;
+        ///
+        /// let urn = Urn::from_str("urn:example:resource#section").unwrap();
+        /// let bytes = urn.to_bytes();
+        /// assert_eq!(Urn::from_bytes(&bytes).unwrap(), urn);
+        /// ```
+        pub fn to_bytes(&self) -> Vec<u8> {
+            let optional = [(HAS_PATH, &self.path), (HAS_QUERY, &self.query), (HAS_FRAGMENT, &self.fragment)];
+            let flags = optional.iter()
+                .filter(|(_, component)| component.is_some())
+                .fold(0, |flags, (flag, _)| flags | flag);
+
+            let mut bytes = vec![flags];
+            write_component(&mut bytes, &self.nid);
+            write_component(&mut bytes, &self.nss);
+            for (_, component) in optional {
+                if let Some(component) = component {
+                    write_component(&mut bytes, component);
+                }
+            }
+            bytes
+        }
+
+        /// Decodes a URN from the binary form produced by [`Urn::to_bytes`].
+        ///
+        /// The decoded components are validated like [`Urn::from_str`] does, so bytes that
+        /// weren't produced by `to_bytes` can't smuggle in a URN whose string form doesn't
+        /// parse back to it, such as one with an empty NID.
+        ///
+        /// # Returns
+        ///
+        /// * `Ok(Urn)` - The decoded URN.
+        /// * `Err(UrnError::InvalidEncoding)` - If the bytes aren't a well-formed encoding,
+        ///   or a component isn't in its percent-encoded form.
+        /// * `Err(UrnError::Format)` - If the components don't form a valid URN.
+        pub fn from_bytes(bytes: &[u8]) -> Result<Urn, UrnError> {
+            let (&flags, mut rest) = bytes.split_first()
+                .ok_or(UrnError::InvalidEncoding("missing flags"))?;
+            if flags & !(HAS_PATH | HAS_QUERY | HAS_FRAGMENT) != 0 {
+                return Err(UrnError::InvalidEncoding("unknown flags"));
+            }
+
+            let optional = |flag: u8, rest: &mut &[u8]| -> Result<Option<Arc<str>>, UrnError> {
+                if flags & flag != 0 { read_component(rest).map(Some) } else { Ok(None) }
+            };
+
+            let urn = Urn {
+                nid: read_component(&mut rest)?,
+                nss: read_component(&mut rest)?,
+                path: optional(HAS_PATH, &mut rest)?,
+                query: optional(HAS_QUERY, &mut rest)?,
+                fragment: optional(HAS_FRAGMENT, &mut rest)?,
+                raw: None,
+                builder_settings: BuilderOnly,
+            };
+            if !rest.is_empty() {
+                return Err(UrnError::InvalidEncoding("trailing bytes"));
+            }
+
+            // The string form goes through the same validation as any parsed URN
+            if Urn::from_str(&urn.to_string())? != urn {
+                return Err(UrnError::InvalidEncoding("component is not percent-encoded"));
+            }
+            Ok(urn)
+        }
+    }
+
+    /// Appends a length-prefixed component to `bytes`.
+    fn write_component(bytes: &mut Vec<u8>, component: &str) {
+        let mut len = component.len();
+        while len >= 0x80 {
+            bytes.push((len as u8 & 0x7F) | 0x80);
+            len >>= 7;
+        }
+        bytes.push(len as u8);
+        bytes.extend_from_slice(component.as_bytes());
+    }
+
+    /// Reads a length-prefixed component from the start of `bytes`, advancing past it.
+    fn read_component(bytes: &mut &[u8]) -> Result<Arc<str>, UrnError> {
+        let mut len: usize = 0;
+        let mut shift = 0;
+        loop {
+            let (&byte, rest) = bytes.split_first()
+                .ok_or(UrnError::InvalidEncoding("truncated length"))?;
+            *bytes = rest;
+            if shift >= usize::BITS {
+                return Err(UrnError::InvalidEncoding("length overflow"));
+            }
+            len |= ((byte & 0x7F) as usize) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+
+        if bytes.len() < len {
+            return Err(UrnError::InvalidEncoding("truncated component"));
+        }
+        let (component, rest) = bytes.split_at(len);
+        *bytes = rest;
+        std::str::from_utf8(component)
+            .map(Arc::from)
+            .map_err(|_| UrnError::InvalidEncoding("component is not valid UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Converts borrowed key-value pairs to the owned ones of [`Urn::query_pairs`].
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_from_string() {
+        let urn = Urn::from_str("urn:some_nid:foo.bar/hello?foo=bar&flip=flop#world").unwrap();
+        assert_eq!(urn.nid(), "some_nid");
+        assert_eq!(urn.nss(), "foo.bar");
+        assert_eq!(urn.path(), Some("hello"));
+        assert_eq!(urn.query_pairs(), pairs(&[("foo", "bar"), ("flip", "flop")]));
+        assert_eq!(urn.fragment(), Some("world"));
+    }
+
+    #[test]
+    fn test_no_scheme() {
+        match Urn::from_str("some_nid:foo.bar/hello?foo=bar&flip=flop#world") {
+            Err(UrnFormatError::UrnSchemeExpected) => {}
+            _ => panic!("Expected UrnFormatError::UrnSchemeExpected"),
+        }
+    }
+
+    #[test]
     fn test_display() {
+        // Underscores aren't allowed in an NID by RFC 8141, only by the parser
+        let urn = Urn::builder()
+            .nid("some_nid")
+            .nss("foo.bar")
+            .path("hello")
+            .query("foo=bar&flip=flop")
+            .fragment("world")
+            .strict(false)
+            .build().unwrap();
+
+        assert_eq!(
+            "urn:some_nid:foo.bar/hello?foo=bar&flip=flop#world",
+            urn.to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_to() {
+        let urns = [
+            Urn::from_str("urn:example:first").unwrap(),
+            Urn::from_str("urn:example:second/path?key=value").unwrap(),
+            Urn::from_str("urn:example:third#section").unwrap(),
+        ];
+
+        let mut out = String::new();
+        for urn in &urns {
+            urn.write_to(&mut out).unwrap();
+            out.push('\n');
+        }
+
+        assert_eq!(
+            out,
+            "urn:example:first\nurn:example:second/path?key=value\nurn:example:third#section\n"
+        );
+    }
+
+    #[test]
+    fn test_nss_with_colons_round_trip() {
+        let urn = Urn::from_str("urn:example:foo:bar:baz").unwrap();
+        assert_eq!(urn.nid(), "example");
+        assert_eq!(urn.nss(), "foo:bar:baz");
+        assert_eq!(urn.to_string(), "urn:example:foo:bar:baz");
+
+        let built = Urn::builder().nid("example").nss("foo:bar:baz").build().unwrap();
+        let reparsed = Urn::from_str(&built.to_string()).unwrap();
+        assert_eq!(reparsed.nss(), "foo:bar:baz");
+        assert_eq!(reparsed, built);
+    }
+
+    #[test]
+    fn test_nss_with_delimiters_round_trip() {
+        let built = Urn::builder().nid("example").nss("a/b?c#d").build().unwrap();
+        assert_eq!(built.to_string(), "urn:example:a%2Fb%3Fc%23d");
+
+        let reparsed = Urn::from_str(&built.to_string()).unwrap();
+        assert_eq!(reparsed.nss(), "a%2Fb%3Fc%23d");
+        assert_eq!(reparsed.path(), None);
+        assert_eq!(reparsed.to_string(), built.to_string());
+        assert_eq!(reparsed, built);
+        assert_eq!(reparsed.decoded_nss(), "a/b?c#d");
+    }
+
+    #[test]
+    fn test_query_round_trip() {
+        let inputs = [
+            ("urn:example:x?a[]=1", "urn:example:x?a%5B%5D=1"),
+            ("urn:example:x?a=b|c", "urn:example:x?a=b%7Cc"),
+            ("urn:example:x?a={b}^c`", "urn:example:x?a=%7Bb%7D%5Ec%60"),
+            ("urn:example:x?100%", "urn:example:x?100%25"),
+            ("urn:example:x?a=%7C&b=c", "urn:example:x?a=%7C&b=c"),
+        ];
+        for (input, output) in inputs {
+            let urn = Urn::from_str(input).unwrap();
+            assert_eq!(urn.to_string(), output, "{input}");
+            assert_eq!(Urn::from_str(&urn.to_string()).unwrap(), urn, "{input}");
+        }
+
+        // The decoded pairs are the ones of the input
+        let urn = Urn::from_str("urn:example:x?a[]=b|c").unwrap();
+        assert_eq!(urn.query_pairs(), pairs(&[("a[]", "b|c")]));
+    }
+
+    #[test]
+    fn test_from_str_retaining() {
+        let input = "urn:example:resource/a%2fb%7E?key=value";
+        let urn = Urn::from_str_retaining(input).unwrap();
+        assert_eq!(urn.raw(), Some(input));
+
+        // Equality ignores the raw string
+        let plain = Urn::from_str(input).unwrap();
+        assert_eq!(plain.raw(), None);
+        assert_eq!(urn, plain);
+
+        // Derived URNs no longer match their source
+        assert_eq!(urn.with_fragment(Some("section")).raw(), None);
+    }
+
+    #[test]
+    fn test_minimal_urn() {
+        let urn = Urn::from_str("urn:example:simple").unwrap();
+        assert_eq!(urn.nid(), "example");
+        assert_eq!(urn.nss(), "simple");
+        assert_eq!(urn.path(), None);
+        assert_eq!(urn.parse_query(), None);
+        assert_eq!(urn.fragment(), None);
+    }
+
+    #[test]
+    fn test_urn_with_path() {
+        let urn = Urn::from_str("urn:example:complex/path/to/resource").unwrap();
+        assert_eq!(urn.nid(), "example");
+        assert_eq!(urn.nss(), "complex");
+        assert_eq!(urn.path(), Some("path/to/resource"));
+        assert_eq!(urn.parse_query(), None);
+        assert_eq!(urn.fragment(), None);
+    }
+
+    #[test]
+    fn test_path_segments_decoded() {
+        let urn = Urn::from_str("urn:example:x/a%20b/c").unwrap();
+        let segments: Vec<_> = urn.path_segments_decoded().unwrap().collect();
+        assert_eq!(segments, ["a b", "c"]);
+
+        // An encoded slash stays inside its segment
+        let urn = Urn::from_str("urn:example:x/a%2Fb/c").unwrap();
+        let segments: Vec<_> = urn.path_segments_decoded().unwrap().collect();
+        assert_eq!(segments, ["a/b", "c"]);
+
+        let urn = Urn::from_str("urn:example:x").unwrap();
+        assert!(urn.path_segments_decoded().is_none());
+    }
+
+    #[test]
+    fn test_segments_keep_encoded_separators() {
+        let encoded = Urn::from_str("urn:example:x/a%2Fb").unwrap();
+        let separated = Urn::from_str("urn:example:x/a/b").unwrap();
+
+        assert_eq!(encoded.path_segments().unwrap().collect::<Vec<_>>(), ["a%2Fb"]);
+        assert_eq!(separated.path_segments().unwrap().collect::<Vec<_>>(), ["a", "b"]);
+        assert!(!encoded.is_path_equivalent(&separated));
+
+        // Hex case doesn't matter once decoded
+        let lowercase = Urn::from_str("urn:example:x/a%2fb").unwrap();
+        assert!(encoded.is_path_equivalent(&lowercase));
+
+        let no_path = Urn::from_str("urn:example:x").unwrap();
+        assert!(no_path.path_segments().is_none());
+        assert!(!no_path.is_path_equivalent(&encoded));
+    }
+
+    #[test]
+    fn test_invalid_percent_encoding() {
+        assert!(matches!(
+            Urn::from_str("urn:example:x/a%zzb"),
+            Err(UrnFormatError::InvalidPercentEncoding)
+        ));
+        assert!(matches!(
+            Urn::from_str("urn:example:x/a%2"),
+            Err(UrnFormatError::InvalidPercentEncoding)
+        ));
+    }
+
+    #[test]
+    fn test_urn_with_query() {
+        let urn = Urn::from_str("urn:example:resource?key1=value1&key2=value2").unwrap();
+        assert_eq!(urn.nid(), "example");
+        assert_eq!(urn.nss(), "resource");
+        assert_eq!(urn.path(), None);
+        assert_eq!(urn.query_pairs(), pairs(&[("key1", "value1"), ("key2", "value2")]));
+        assert_eq!(urn.fragment(), None);
+    }
+
+    #[test]
+    fn test_urn_with_fragment() {
+        let urn = Urn::from_str("urn:example:resource#section1").unwrap();
+        assert_eq!(urn.nid(), "example");
+        assert_eq!(urn.nss(), "resource");
+        assert_eq!(urn.path(), None);
+        assert_eq!(urn.parse_query(), None);
+        assert_eq!(urn.fragment(), Some("section1"));
+    }
+
+    #[test]
+    fn test_fragment_validation() {
+        let urn = Urn::from_str("urn:example:resource#a/b?c:d@e%20f").unwrap();
+        assert_eq!(urn.fragment(), Some("a/b?c:d@e%20f"));
+
+        assert!(matches!(
+            Urn::from_str("urn:example:resource#sec tion"),
+            Err(UrnFormatError::InvalidFragment { position: 24 })
+        ));
+        assert!(matches!(
+            Urn::from_str("urn:example:resource#a#b"),
+            Err(UrnFormatError::InvalidFragment { position: 22 })
+        ));
+    }
+
+    #[test]
+    fn test_with_fragment_encodes() {
+        let urn = Urn::from_str("urn:example:resource").unwrap();
+
+        let with_fragment = urn.with_fragment(Some("sec tion#2"));
+        assert_eq!(with_fragment.fragment(), Some("sec%20tion%232"));
+
+        // Existing percent-encoding is not encoded twice
+        let with_fragment = urn.with_fragment(Some("sec%20tion"));
+        assert_eq!(with_fragment.fragment(), Some("sec%20tion"));
+
+        let reparsed = Urn::from_str(&urn.with_fragment(Some("a b")).to_string()).unwrap();
+        assert_eq!(reparsed.fragment(), Some("a%20b"));
+    }
+
+    #[test]
+    fn test_invalid_urn_scheme() {
+        assert!(matches!(
+            Urn::from_str("http:example:resource"),
+            Err(UrnFormatError::UrnSchemeExpected)
+        ));
+    }
+
+    // Add a new test for invalid URN format
+    #[test]
+    fn test_invalid_urn_format() {
+        assert!(matches!(
+            Urn::from_str("urn:invalid"),
+            Err(UrnFormatError::InvalidUrn)
+        ));
+    }
+
+    #[test]
+    fn test_builder() {
         let urn = Urn::builder()
-            .nid("some_nid")
-            .nss("foo.bar")
-            .path("hello")
-            .query("foo=bar&flip=flop")
-            .fragment("world")
-            .build().unwrap();
+            .nid("example")
+            .nss("resource")
+            .path("path")
+            .query("key=value")
+            .fragment("section")
+            .build()
+            .unwrap();
+
+        assert_eq!(urn.to_string(), "urn:example:resource/path?key=value#section");
+    }
+
+    #[test]
+    fn test_try_build() {
+        let urn = Urn::builder().nid("example").nss("resource").try_build().unwrap();
+        assert_eq!(urn.to_string(), "urn:example:resource");
+
+        assert!(matches!(
+            Urn::builder().nid("example").try_build(),
+            Err(UrnError::MissingComponent("nss"))
+        ));
+        assert!(matches!(
+            Urn::builder().nid("").nss("resource").try_build(),
+            Err(UrnError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_to_builder() {
+        let urn = Urn::from_str("urn:example:resource/path?key=value#section").unwrap();
+        let derived = urn.to_builder()
+            .nss("other")
+            .fragment("new")
+            .build()
+            .unwrap();
+
+        assert_eq!(derived.to_string(), "urn:example:other/path?key=value#new");
+        // The original is left untouched
+        assert_eq!(urn.to_string(), "urn:example:resource/path?key=value#section");
+    }
+
+    #[test]
+    fn test_is_valid() {
+        let valid_urn = Urn::from_str("urn:example:valid").unwrap();
+        assert!(valid_urn.is_valid());
+
+        let invalid_urn = Urn::builder().nid("").nss("invalid").strict(false).build().unwrap();
+        assert!(!invalid_urn.is_valid());
+    }
+
+    #[test]
+    fn test_is_relative() {
+        let absolute = Urn::from_str("urn:example:resource/path").unwrap();
+        assert!(absolute.is_absolute());
+        assert!(!absolute.is_relative());
+
+        let relative = Urn::builder().nid("").nss("").path("other").query("k=v").strict(false).build().unwrap();
+        assert!(relative.is_relative());
+        assert!(!relative.is_absolute());
+
+        assert_eq!(relative.resolve(&absolute).to_string(), "urn:example:resource/other?k=v");
+        assert_eq!(absolute.resolve(&absolute), absolute);
+    }
+
+    #[test]
+    fn test_to_url() {
+        let urn = Urn::from_str("urn:example:resource?query=value#fragment").unwrap();
+        let url = urn.to_url().unwrap();
+        assert_eq!(url.as_str(), "urn:example:resource?query=value#fragment");
+    }
+
+    #[test]
+    fn test_equals() {
+        let urn1 = Urn::from_str("urn:EXAMPLE:resource").unwrap();
+        let urn2 = Urn::from_str("urn:example:resource").unwrap();
+        let urn3 = Urn::from_str("urn:example:different").unwrap();
+
+        assert!(urn1.equals(&urn2));
+        assert!(!urn1.equals(&urn3));
+    }
+
+    #[test]
+    fn test_eq_operator() {
+        let upper = Urn::from_str("urn:EXAMPLE:resource").unwrap();
+        let lower = Urn::from_str("urn:example:resource").unwrap();
+        let nss_case = Urn::from_str("urn:example:RESOURCE").unwrap();
+
+        // The NID is case-insensitive
+        assert_eq!(upper, lower);
+        // The NSS is case-sensitive
+        assert_ne!(lower, nss_case);
+        // Query and fragment are part of equality
+        assert_ne!(lower, lower.with_fragment(Some("section")));
+    }
+
+    #[test]
+    fn test_hash_consistent_with_eq() {
+        use std::collections::HashSet;
+
+        let mut set = HashSet::new();
+        set.insert(Urn::from_str("urn:EXAMPLE:resource").unwrap());
+        set.insert(Urn::from_str("urn:example:resource").unwrap());
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_hash_set_equality() {
+        use std::collections::HashSet;
+
+        let inputs = ["urn:EXAMPLE:x", "urn:example:x", "urn:Example:x?k=v", "urn:example:X"];
+        let urns: Vec<Urn> = inputs.iter().map(|s| Urn::from_str(s).unwrap()).collect();
+
+        // `HashSet<Urn>` uses `equals`: NID case is ignored, the rest isn't
+        let set: HashSet<Urn> = urns.iter().cloned().collect();
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&Urn::from_str("urn:eXaMpLe:x").unwrap()));
+
+        // `HashSet<UrnKey>` uses lexical equivalence, ignoring the query as well
+        let keys: HashSet<UrnKey> = urns.into_iter().map(UrnKey::from).collect();
+        assert_eq!(keys.len(), 2);
+    }
+
+    #[test]
+    fn test_with_shares_components() {
+        let urn = Urn::from_str("urn:example:resource/path").unwrap();
+        let derived = urn.with_query(Some("key=value")).with_fragment(Some("section"));
+
+        assert!(Arc::ptr_eq(&urn.nss, &derived.nss));
+        assert!(Arc::ptr_eq(urn.path.as_ref().unwrap(), derived.path.as_ref().unwrap()));
+        assert_eq!(derived.to_string(), "urn:example:resource/path?key=value#section");
+    }
+
+    #[test]
+    fn test_urn_key() {
+        use std::collections::HashMap;
+
+        let mut map = HashMap::new();
+        map.insert(UrnKey::from(Urn::from_str("urn:EXAMPLE:resource/path").unwrap()), "value");
+
+        // Another casing of the NID hits the same entry
+        let lookup = UrnKey::from(Urn::from_str("urn:example:resource/path").unwrap());
+        assert_eq!(map.get(&lookup), Some(&"value"));
+
+        // Query and fragment don't take part in lexical equivalence
+        let lookup = UrnKey::from(Urn::from_str("urn:Example:resource/path?k=v#f").unwrap());
+        assert_eq!(map.get(&lookup), Some(&"value"));
+
+        let lookup = UrnKey::from(Urn::from_str("urn:example:resource/other").unwrap());
+        assert_eq!(map.get(&lookup), None);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let urn = Urn::from_str("urn:EXAMPLE:resource").unwrap();
+        let normalized = urn.normalize();
+        assert_eq!(normalized.nid(), "example");
+    }
+
+    #[test]
+    fn test_with_query() {
+        let urn = Urn::from_str("urn:example:resource").unwrap();
+        let with_query = urn.with_query(Some("key=value"));
+        assert_eq!(with_query.query_pairs(), pairs(&[("key", "value")]));
+    }
+
+    #[test]
+    fn test_with_fragment() {
+        let urn = Urn::from_str("urn:example:resource").unwrap();
+        let with_fragment = urn.with_fragment(Some("section1"));
+        assert_eq!(with_fragment.fragment(), Some("section1"));
+    }
+
+    #[test]
+    fn test_without_query() {
+        let urn = Urn::from_str("urn:example:resource?key=value").unwrap();
+        let without_query = urn.without_query();
+        assert_eq!(without_query.parse_query(), None);
+    }
+
+    #[test]
+    fn test_without_fragment() {
+        let urn = Urn::from_str("urn:example:resource#section1").unwrap();
+        let without_fragment = urn.without_fragment();
+        assert_eq!(without_fragment.fragment(), None);
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let urn = Urn::from_str("urn:example:resource?key1=value1&key2=value2").unwrap();
+        let query_map = urn.parse_query().unwrap();
+        assert_eq!(query_map.get("key1"), Some(&"value1".to_string()));
+        assert_eq!(query_map.get("key2"), Some(&"value2".to_string()));
+    }
+
+    #[test]
+    fn test_is_lexically_equivalent() {
+        let urn1 = Urn::from_str("urn:EXAMPLE:resource").unwrap();
+        let urn2 = Urn::from_str("urn:example:resource").unwrap();
+        let urn3 = Urn::from_str("urn:example:resource?query=value#fragment").unwrap();
+        let urn4 = Urn::from_str("urn:example:different").unwrap();
+
+        assert!(urn1.is_lexically_equivalent(&urn2));
+        assert!(urn1.is_lexically_equivalent(&urn3)); // query and fragment don't affect lexical equivalence
+        assert!(!urn1.is_lexically_equivalent(&urn4));
+    }
+
+    #[test]
+    #[cfg(feature = "binary")]
+    fn test_binary_round_trip() {
+        let long_path = "a/".repeat(100) + "end";
+        let inputs = [
+            "urn:example:resource".to_string(),
+            "urn:example:resource/path".to_string(),
+            "urn:example:resource?key=value".to_string(),
+            "urn:example:resource#section".to_string(),
+            "urn:example:resource/path?key=value#section".to_string(),
+            format!("urn:example:resource/{}", long_path),
+        ];
+
+        for input in inputs {
+            let urn = Urn::from_str(&input).unwrap();
+            let decoded = Urn::from_bytes(&urn.to_bytes()).unwrap();
+            assert_eq!(decoded, urn);
+            assert_eq!(decoded.to_string(), input);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "binary")]
+    fn test_binary_round_trip_of_parsed_query() {
+        let urn = Urn::from_str("urn:example:resource?a[]=b|c").unwrap();
+        let decoded = Urn::from_bytes(&urn.to_bytes()).unwrap();
+        assert_eq!(decoded, urn);
+        assert_eq!(decoded.query_pairs(), pairs(&[("a[]", "b|c")]));
+    }
+
+    #[test]
+    #[cfg(feature = "binary")]
+    fn test_binary_invalid() {
+        let bytes = Urn::from_str("urn:example:resource/path").unwrap().to_bytes();
+
+        assert!(matches!(Urn::from_bytes(&[]), Err(UrnError::InvalidEncoding(_))));
+        assert!(matches!(Urn::from_bytes(&bytes[..bytes.len() - 1]), Err(UrnError::InvalidEncoding(_))));
+        assert!(matches!(Urn::from_bytes(&[bytes.as_slice(), &[0]].concat()), Err(UrnError::InvalidEncoding(_))));
+        assert!(matches!(Urn::from_bytes(&[0b1000, 0, 0]), Err(UrnError::InvalidEncoding(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "binary")]
+    fn test_binary_rejects_invalid_components() {
+        let encode = |nid: &str, nss: &str| {
+            let urn = Urn {
+                nid: Arc::from(nid),
+                nss: Arc::from(nss),
+                path: None,
+                query: None,
+                fragment: None,
+                raw: None,
+                builder_settings: BuilderOnly,
+            };
+            urn.to_bytes()
+        };
 
+        assert!(Urn::from_bytes(&encode("example", "resource")).is_ok());
+        // An empty or illegal NID can't be written and parsed back
+        assert!(matches!(Urn::from_bytes(&encode("", "resource")), Err(UrnError::Format(_))));
+        assert!(matches!(Urn::from_bytes(&encode("a b", "resource")), Err(UrnError::Format(_))));
+        assert!(matches!(Urn::from_bytes(&encode("example", "")), Err(UrnError::Format(_))));
+        // Neither can an NSS that isn't percent-encoded
+        assert!(matches!(Urn::from_bytes(&encode("example", "a b")), Err(UrnError::InvalidEncoding(_))));
+    }
+
+    fn parse_all(urns: &[&str]) -> Vec<Urn> {
+        urns.iter().map(|s| Urn::from_str(s).unwrap()).collect()
+    }
+
+    fn sorted_strings(mut urns: Vec<Urn>, comparator: UrnComparator) -> Vec<String> {
+        urns.sort_by(|a, b| comparator.cmp(a, b));
+        urns.dedup_by(|a, b| comparator.eq(a, b));
+        urns.iter().map(Urn::to_string).collect()
+    }
+
+    #[test]
+    fn test_comparator_strict() {
+        let urns = parse_all(&[
+            "urn:example:b",
+            "urn:EXAMPLE:a",
+            "urn:example:a?v=1",
+            "urn:example:a",
+            "urn:example:a",
+        ]);
         assert_eq!(
-            "urn:some_nid:foo.bar/hello?foo=bar&flip=flop#world",
-            urn.to_string()
+            sorted_strings(urns, UrnComparator::Strict),
+            ["urn:EXAMPLE:a", "urn:example:a", "urn:example:a?v=1", "urn:example:b"]
         );
     }
 
     #[test]
-    fn test_minimal_urn() {
-        let urn = Urn::from_str("urn:example:simple").unwrap();
-        assert_eq!(urn.nid(), "example");
-        assert_eq!(urn.nss(), "simple");
-        assert_eq!(urn.path(), None);
-        assert_eq!(urn.query(), None);
-        assert_eq!(urn.fragment(), None);
+    fn test_comparator_lexical() {
+        let urns = parse_all(&[
+            "urn:example:b",
+            "urn:EXAMPLE:a",
+            "urn:example:a?v=1",
+            "urn:example:A",
+            "urn:example:a/path",
+        ]);
+        assert_eq!(
+            sorted_strings(urns, UrnComparator::Lexical),
+            ["urn:example:A", "urn:EXAMPLE:a", "urn:example:a/path", "urn:example:b"]
+        );
     }
 
     #[test]
-    fn test_urn_with_path() {
-        let urn = Urn::from_str("urn:example:complex/path/to/resource").unwrap();
-        assert_eq!(urn.nid(), "example");
-        assert_eq!(urn.nss(), "complex");
-        assert_eq!(urn.path(), Some("path/to/resource"));
-        assert_eq!(urn.query(), None);
-        assert_eq!(urn.fragment(), None);
+    fn test_comparator_case_insensitive() {
+        let urns = parse_all(&[
+            "urn:example:b",
+            "urn:EXAMPLE:a",
+            "urn:example:A",
+            "urn:example:a?v=1",
+            "urn:example:a?V=1",
+        ]);
+        assert_eq!(
+            sorted_strings(urns, UrnComparator::CaseInsensitive),
+            ["urn:EXAMPLE:a", "urn:example:a?v=1", "urn:example:b"]
+        );
     }
 
     #[test]
-    fn test_urn_with_query() {
-        let urn = Urn::from_str("urn:example:resource?key1=value1&key2=value2").unwrap();
-        assert_eq!(urn.nid(), "example");
-        assert_eq!(urn.nss(), "resource");
-        assert_eq!(urn.path(), None);
-        assert_eq!(urn.query(), Some("key1=value1&key2=value2"));
-        assert_eq!(urn.fragment(), None);
+    fn test_from_str_fast_matches_from_str() {
+        let inputs = [
+            "urn:example:resource",
+            "urn:example:resource/path/to/item",
+            "urn:example:a%2Fb:c/d%20e",
+            "urn:example:resource?key=value&other=1",
+            "urn:example:resource/path?key=value#section",
+            "urn:example:resource#frag/with?chars",
+            "urn:example:resource?#",
+            "urn:example:resource?a b",
+            "urn:example:resource?a[]=b|c",
+            "urn:example:resource?100%",
+            "urn:other:resource/path",
+            "urn:EXAMPLE:resource",
+        ];
+        for input in inputs {
+            let fast = Urn::from_str_fast(input, "example").unwrap();
+            let full = Urn::from_str(input).unwrap();
+            assert_eq!(fast, full, "{input}");
+            assert_eq!(fast.nid(), full.nid(), "{input}");
+            assert_eq!(fast.path(), full.path(), "{input}");
+            assert_eq!(fast.query_pairs(), full.query_pairs(), "{input}");
+            assert_eq!(fast.fragment(), full.fragment(), "{input}");
+        }
     }
 
     #[test]
-    fn test_urn_with_fragment() {
-        let urn = Urn::from_str("urn:example:resource#section1").unwrap();
-        assert_eq!(urn.nid(), "example");
-        assert_eq!(urn.nss(), "resource");
-        assert_eq!(urn.path(), None);
-        assert_eq!(urn.query(), None);
-        assert_eq!(urn.fragment(), Some("section1"));
+    fn test_from_str_fast_errors() {
+        let inputs = [
+            "urn:example:",
+            "urn:example:bad%zz",
+            "urn:example:resource#bad fragment",
+            "urn:example:resource/bad_path",
+            "example:resource",
+        ];
+        for input in inputs {
+            assert_eq!(
+                format!("{:?}", Urn::from_str_fast(input, "example").unwrap_err()),
+                format!("{:?}", Urn::from_str(input).unwrap_err()),
+                "{input}"
+            );
+        }
     }
 
     #[test]
-    fn test_invalid_urn_scheme() {
+    fn test_validate_against_custom_validator() {
+        struct RejectAll;
+
+        impl NidValidator for RejectAll {
+            fn validate_nss(&self, nss: &str) -> Result<(), String> {
+                Err(format!("rejected {nss}"))
+            }
+        }
+
+        let mut validators = NidValidatorRegistry::new();
+        validators.register("Custom", RejectAll);
+
+        // Runs for its NID, whatever the case
+        let urn = Urn::from_str("urn:custom:resource").unwrap();
         assert!(matches!(
-            Urn::from_str("http:example:resource"),
-            Err(UrnFormatError::UrnSchemeExpected)
+            urn.validate_against(&validators),
+            Err(UrnError::Validation(message)) if message == "rejected resource"
         ));
+        let urn = Urn::from_str("urn:CUSTOM:resource").unwrap();
+        assert!(urn.validate_against(&validators).is_err());
+
+        // Skipped for other NIDs
+        let urn = Urn::from_str("urn:other:resource").unwrap();
+        assert!(urn.validate_against(&validators).is_ok());
     }
 
-    // Add a new test for invalid URN format
     #[test]
-    fn test_invalid_urn_format() {
+    fn test_builtin_validators() {
+        let validators = NidValidatorRegistry::with_builtins();
+        let check = |s: &str| Urn::from_str(s).unwrap().validate_against(&validators).is_ok();
+
+        assert!(check("urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6"));
+        assert!(check("urn:uuid:F81D4FAE-7DEC-11D0-A765-00A0C91E6BF6"));
+        assert!(!check("urn:uuid:f81d4fae7dec11d0a76500a0c91e6bf6"));
+        assert!(!check("urn:uuid:g81d4fae-7dec-11d0-a765-00a0c91e6bf6"));
+
+        assert!(check("urn:oid:1.3.6.1.4.1"));
+        assert!(check("urn:oid:0"));
+        assert!(!check("urn:oid:1..3"));
+        assert!(!check("urn:oid:1.03"));
+        assert!(!check("urn:oid:1.a"));
+    }
+
+    #[test]
+    fn test_builder_strict_and_lenient() {
+        // Strict by default
         assert!(matches!(
-            Urn::from_str("urn:invalid"),
-            Err(UrnFormatError::InvalidUrn)
+            Urn::builder().nid("").nss("resource").build(),
+            Err(UrnBuilderError::ValidationError(_))
         ));
+        assert!(Urn::builder().nid("").nss("resource").strict(true).build().is_err());
+        assert!(Urn::builder().nid("example").nss("").build().is_err());
+
+        let lenient = Urn::builder().nid("").nss("resource").strict(false).build().unwrap();
+        assert_eq!(lenient.nid(), "");
+        assert!(!lenient.is_valid());
+
+        // Missing components are an error in both modes
+        assert!(matches!(
+            Urn::builder().nid("example").strict(false).build(),
+            Err(UrnBuilderError::UninitializedField("nss"))
+        ));
+
+        // The mode isn't carried over from the URN
+        assert!(lenient.to_builder().build().is_err());
+        assert_eq!(lenient.raw(), None);
     }
 
     #[test]
-    fn test_builder() {
+    fn test_did_round_trip() {
+        let dids = [
+            "did:example:123456789abcdefghi",
+            "did:web:example.com:user:alice",
+            "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK",
+            "did:example:a%20b",
+        ];
+        for did in dids {
+            let urn = Urn::from_did(did).unwrap();
+            assert_eq!(urn.nid(), "did");
+            assert_eq!(urn.as_did().as_deref(), Some(did));
+
+            // The URN form parses back to the same DID
+            let reparsed = Urn::from_str(&urn.to_string()).unwrap();
+            assert_eq!(reparsed, urn);
+            assert_eq!(reparsed.as_did().as_deref(), Some(did));
+            assert_eq!(Urn::from_did(&urn.to_string()).unwrap(), urn);
+        }
+    }
+
+    #[test]
+    fn test_invalid_did() {
+        for did in ["example:123", "did:", "did:example", "did:example:", "did:Example:123",
+                    "did:example:123:", "did:example:a/b", "did:example:a#b"] {
+            assert!(matches!(Urn::from_did(did), Err(UrnError::Validation(_))), "{did}");
+        }
+
+        assert_eq!(Urn::from_str("urn:example:method:id").unwrap().as_did(), None);
+        assert_eq!(Urn::from_str("urn:did:nomethod").unwrap().as_did(), None);
+    }
+
+    #[test]
+    fn test_cutoff_round_trip() {
+        type Constructor = fn(&str) -> Result<Urn, UrnError>;
+        type Parser = fn(&Urn) -> Option<&str>;
+        let kinds: [(&str, Constructor, Parser); 2] = [
+            ("project", Urn::cutoff_project, Urn::as_cutoff_project),
+            ("track", Urn::cutoff_track, Urn::as_cutoff_track),
+        ];
+        for (kind, construct, parse) in kinds {
+            for id in ["42", "0b1e5a3c-7d2f-4e8a-9c61-2f0d8b7e4a19", "Mix-Down"] {
+                let urn = construct(id).unwrap();
+                assert_eq!(urn.to_string(), format!("urn:cutoff:{kind}/{id}"));
+                assert_eq!(parse(&urn), Some(id));
+
+                // The string form parses back to the same resource
+                let reparsed = Urn::from_str(&urn.to_string()).unwrap();
+                assert_eq!(reparsed, urn);
+                assert_eq!(parse(&reparsed), Some(id));
+            }
+        }
+
+        let track = Urn::cutoff_track("42").unwrap();
+        assert_eq!(track.as_cutoff_project(), None);
+        assert_eq!(Urn::from_str("urn:CUTOFF:track/42#t=5").unwrap().as_cutoff_track(), Some("42"));
+    }
+
+    #[test]
+    fn test_invalid_cutoff_id() {
+        for id in ["", "a/b", "a b", "a%20b", "caf\u{e9}", "a_b"] {
+            assert!(matches!(Urn::cutoff_project(id), Err(UrnError::Validation(_))), "{id}");
+            assert!(matches!(Urn::cutoff_track(id), Err(UrnError::Validation(_))), "{id}");
+        }
+
+        for urn in ["urn:cutoff:project", "urn:cutoff:project/a/b", "urn:cutoff:project/",
+                    "urn:cutoff:Project/42", "urn:example:project/42"] {
+            assert_eq!(Urn::from_str(urn).unwrap().as_cutoff_project(), None, "{urn}");
+        }
+    }
+
+    #[test]
+    fn test_component_diff() {
+        let a = Urn::from_str("urn:example:resource/path?k=v#one").unwrap();
+        let b = Urn::from_str("urn:example:resource/path?k=v#two").unwrap();
+        assert_eq!(a.component_diff(&b), [UrnComponent::Fragment]);
+
+        // The NID is compared case-insensitively, like `==`
+        let upper = Urn::from_str("urn:EXAMPLE:resource/path?k=v#one").unwrap();
+        assert!(a.component_diff(&upper).is_empty());
+
+        // Including non-ASCII NIDs from a lenient builder
+        let lenient = |nid: &str| Urn::builder().strict(false).nid(nid).nss("x").build().unwrap();
+        assert_eq!(lenient("ÉTÉ"), lenient("été"));
+        assert!(lenient("ÉTÉ").component_diff(&lenient("été")).is_empty());
+
+        let c = Urn::from_str("urn:other:different").unwrap();
+        assert_eq!(
+            a.component_diff(&c),
+            [UrnComponent::Nid, UrnComponent::Nss, UrnComponent::Path, UrnComponent::Query, UrnComponent::Fragment]
+        );
+        assert_eq!(UrnComponent::Nss.to_string(), "NSS");
+    }
+
+    #[test]
+    // The merged query string keeps the order of the existing pairs
+    #[allow(deprecated)]
+    fn test_with_query_defaults() {
+        let urn = Urn::from_str("urn:example:resource?b=2&a=1").unwrap();
+        let merged = urn.with_query_defaults(&[("a", "default"), ("c", "3"), ("b", "x"), ("d", "a b"), ("c", "4")]);
+
+        // Existing values and order are kept, missing keys are appended
+        assert_eq!(merged.query(), Some("b=2&a=1&c=3&d=a+b"));
+        assert_eq!(merged.parse_query().unwrap()["d"], "a b");
+
+        // Nothing to add leaves the URN as it is
+        assert_eq!(urn.with_query_defaults(&[("a", "x")]), urn);
+
+        let no_query = Urn::from_str("urn:example:resource").unwrap();
+        assert_eq!(no_query.with_query_defaults(&[("k", "v")]).query(), Some("k=v"));
+        assert_eq!(no_query.with_query_defaults(&[]).query(), None);
+    }
+
+    #[test]
+    // A plain query is only reachable through the whole query
+    #[allow(deprecated)]
+    fn test_rq_components() {
+        let urn = Urn::from_str("urn:example:foo?+res?=k=v#frag").unwrap();
+        assert_eq!(urn.rcomponent(), Some("res"));
+        assert_eq!(urn.qcomponent(), Some("k=v"));
+        assert_eq!(urn.fragment(), Some("frag"));
+        assert_eq!(urn.to_string(), "urn:example:foo?+res?=k=v#frag");
+
+        let urn = Urn::from_str("urn:example:foo/path?+res").unwrap();
+        assert_eq!(urn.rcomponent(), Some("res"));
+        assert_eq!(urn.qcomponent(), None);
+        assert_eq!(urn.to_string(), "urn:example:foo/path?+res");
+
+        let urn = Urn::from_str("urn:example:foo?=a=1&b=2").unwrap();
+        assert_eq!(urn.rcomponent(), None);
+        assert_eq!(urn.qcomponent(), Some("a=1&b=2"));
+        assert_eq!(urn.to_string(), "urn:example:foo?=a=1&b=2");
+
+        // A plain query is neither, but is still available as a whole
+        let urn = Urn::from_str("urn:example:foo?k=v").unwrap();
+        assert_eq!(urn.rcomponent(), None);
+        assert_eq!(urn.qcomponent(), None);
+        assert_eq!(urn.query(), Some("k=v"));
+        assert_eq!(urn.to_string(), "urn:example:foo?k=v");
+    }
+
+    #[test]
+    fn test_builder_rq_components() {
         let urn = Urn::builder()
             .nid("example")
-            .nss("resource")
-            .path("path")
-            .query("key=value")
-            .fragment("section")
+            .nss("foo")
+            .rcomponent("res")
+            .qcomponent("k=v")
+            .fragment("frag")
             .build()
             .unwrap();
+        assert_eq!(urn.rcomponent(), Some("res"));
+        assert_eq!(urn.qcomponent(), Some("k=v"));
+        assert_eq!(urn.to_string(), "urn:example:foo?+res?=k=v#frag");
+        assert_eq!(Urn::from_str(&urn.to_string()).unwrap(), urn);
 
-        assert_eq!(urn.to_string(), "urn:example:resource/path?key=value#section");
+        // Setting one component again replaces it, keeping the other
+        let urn = urn.to_builder().rcomponent("other").build().unwrap();
+        assert_eq!(urn.to_string(), "urn:example:foo?+other?=k=v#frag");
+
+        let urn = Urn::builder().nid("example").nss("foo").qcomponent("k=v").build().unwrap();
+        assert_eq!(urn.to_string(), "urn:example:foo?=k=v");
     }
 
     #[test]
-    fn test_is_valid() {
-        let valid_urn = Urn::from_str("urn:example:valid").unwrap();
-        assert!(valid_urn.is_valid());
+    fn test_from_components() {
+        let urn = Urn::from_components("example", "a:b", Some("x/y%20z"), Some("=k=v"), Some("frag")).unwrap();
+        assert_eq!(urn.to_string(), "urn:example:a:b/x/y%20z?=k=v#frag");
+        assert_eq!(Urn::from_str(&urn.to_string()).unwrap(), urn);
 
-        let invalid_urn = Urn::builder().nid("").nss("invalid").build().unwrap();
-        assert!(!invalid_urn.is_valid());
+        let invalid = |nid, nss, path, query, fragment| {
+            match Urn::from_components(nid, nss, path, query, fragment) {
+                Err(UrnError::InvalidComponent { component, position }) => (component, position),
+                other => panic!("unexpected result: {other:?}"),
+            }
+        };
+        assert_eq!(invalid("ex ample", "nss", None, None, None), (UrnComponent::Nid, 2));
+        assert_eq!(invalid("-example", "nss", None, None, None), (UrnComponent::Nid, 0));
+        assert_eq!(invalid("x", "nss", None, None, None), (UrnComponent::Nid, 1));
+        assert_eq!(invalid("example", "", None, None, None), (UrnComponent::Nss, 0));
+        assert_eq!(invalid("example", "a/b", None, None, None), (UrnComponent::Nss, 1));
+        assert_eq!(invalid("example", "nss", Some("a_b"), None, None), (UrnComponent::Path, 1));
+        assert_eq!(invalid("example", "nss", None, Some("k=%zz"), None), (UrnComponent::Query, 2));
+        assert_eq!(invalid("example", "nss", None, None, Some("a#b")), (UrnComponent::Fragment, 1));
+
+        // The first invalid component is reported
+        assert_eq!(invalid("example", "a b", Some("a b"), None, None), (UrnComponent::Nss, 1));
+
+        let err = Urn::from_components("example", "nss", Some("a b"), None, None).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid URN: invalid path at position 1");
     }
 
     #[test]
-    fn test_to_url() {
-        let urn = Urn::from_str("urn:example:resource?query=value#fragment").unwrap();
-        let url = urn.to_url().unwrap();
-        assert_eq!(url.as_str(), "urn:example:resource?query=value#fragment");
+    fn test_expand_template() {
+        let vars = HashMap::from([
+            ("id".to_string(), "42".to_string()),
+            ("name".to_string(), "a/b c".to_string()),
+            ("unused".to_string(), "x".to_string()),
+        ]);
+
+        let urn = Urn::expand_template("urn:cutoff:track/{id}/waveform", &vars).unwrap();
+        assert_eq!(urn.to_string(), "urn:cutoff:track/42/waveform");
+        assert_eq!(urn.as_cutoff_track(), None);
+
+        // Values are encoded, and stay within their segment
+        let urn = Urn::expand_template("urn:cutoff:{id}/{name}{id}#{id}", &vars).unwrap();
+        assert_eq!(urn.to_string(), "urn:cutoff:42/a%2Fb%20c42#42");
+        assert_eq!(urn.path_segments_decoded().unwrap().collect::<Vec<_>>(), ["a/b c42"]);
     }
 
     #[test]
-    fn test_equals() {
-        let urn1 = Urn::from_str("urn:EXAMPLE:resource").unwrap();
-        let urn2 = Urn::from_str("urn:example:resource").unwrap();
-        let urn3 = Urn::from_str("urn:example:different").unwrap();
+    fn test_expand_template_without_placeholders() {
+        let urn = Urn::expand_template("urn:cutoff:project/42", &HashMap::new()).unwrap();
+        assert_eq!(urn, Urn::from_str("urn:cutoff:project/42").unwrap());
+    }
 
-        assert!(urn1.equals(&urn2));
-        assert!(!urn1.equals(&urn3));
+    #[test]
+    fn test_expand_template_errors() {
+        let vars = HashMap::from([("id".to_string(), "42".to_string())]);
+
+        let err = Urn::expand_template("urn:cutoff:track/{id}/{kind}", &vars).unwrap_err();
+        assert!(matches!(&err, UrnError::MissingVariable(name) if name == "kind"), "{err:?}");
+        assert_eq!(err.to_string(), "Invalid URN template: missing variable `kind`");
+
+        let err = Urn::expand_template("urn:cutoff:track/{id", &vars).unwrap_err();
+        assert!(matches!(err, UrnError::Validation(_)), "{err:?}");
+        let err = Urn::expand_template("{id}:cutoff:track", &vars).unwrap_err();
+        assert!(matches!(err, UrnError::Format(_)), "{err:?}");
     }
 
     #[test]
-    fn test_normalize() {
-        let urn = Urn::from_str("urn:EXAMPLE:resource").unwrap();
-        let normalized = urn.normalize();
-        assert_eq!(normalized.nid(), "example");
+    fn test_components_percent_encoded() {
+        let urn = Urn::builder()
+            .nid("example")
+            .nss("foo bar/baz")
+            .path("a b/c_d")
+            .query("k=a b#c")
+            .fragment("x y")
+            .build()
+            .unwrap();
+        let encoded = urn.to_string();
+        assert_eq!(encoded, "urn:example:foo%20bar%2Fbaz/a%20b/c%5Fd?k=a%20b%23c#x%20y");
+
+        let parsed = Urn::from_str(&encoded).unwrap();
+        assert_eq!(parsed.decoded_nss(), "foo bar/baz");
+        assert_eq!(parsed.path_segments_decoded().unwrap().collect::<Vec<_>>(), ["a b", "c_d"]);
+        assert_eq!(parsed.parse_query().unwrap()["k"], "a b#c");
+        // The wire form is stable, and the built URN holds the encoded components too
+        assert_eq!(parsed.to_string(), encoded);
+        assert_eq!(parsed, urn);
+        assert_eq!(urn.nss(), "foo%20bar%2Fbaz");
     }
 
     #[test]
-    fn test_with_query() {
-        let urn = Urn::from_str("urn:example:resource").unwrap();
-        let with_query = urn.with_query(Some("key=value"));
-        assert_eq!(with_query.query(), Some("key=value"));
+    fn test_built_urn_equals_reparsed() {
+        let builders = [
+            Urn::builder().nid("example").nss("foo bar/baz").clone(),
+            Urn::builder().nid("example").nss("a?b#c").path("x y/%41").clone(),
+            Urn::builder().nid("example").nss("100%").query("q=1 2").fragment("f g").clone(),
+        ];
+        for builder in builders {
+            let built = builder.build().unwrap();
+            let reparsed = Urn::from_str(&built.to_string()).unwrap();
+            assert_eq!(reparsed, built);
+            // Building again from the encoded components changes nothing
+            assert_eq!(built.to_builder().build().unwrap(), built);
+        }
+
+        let urn = Urn::from_str("urn:example:resource").unwrap().with_query(Some("k=a b"));
+        assert_eq!(Urn::from_str(&urn.to_string()).unwrap(), urn);
     }
 
     #[test]
-    fn test_with_fragment() {
-        let urn = Urn::from_str("urn:example:resource").unwrap();
-        let with_fragment = urn.with_fragment(Some("section1"));
-        assert_eq!(with_fragment.fragment(), Some("section1"));
+    fn test_no_double_encoding() {
+        let encoded = "urn:example:foo%20bar/a%2Fb?k=%41#x%20y";
+        let urn = Urn::from_str(encoded).unwrap();
+        assert_eq!(urn.to_string(), encoded);
+
+        let built = Urn::builder().nid("example").nss("100%25").build().unwrap();
+        assert_eq!(built.to_string(), "urn:example:100%25");
+        assert_eq!(Urn::from_str(&built.to_string()).unwrap().decoded_nss(), "100%");
+
+        assert!(matches!(
+            Urn::from_str("urn:example:foo%2"),
+            Err(UrnFormatError::InvalidPercentEncoding)
+        ));
     }
 
+    #[cfg(feature = "testutil")]
     #[test]
-    fn test_without_query() {
-        let urn = Urn::from_str("urn:example:resource?key=value").unwrap();
-        let without_query = urn.without_query();
-        assert_eq!(without_query.query(), None);
+    fn test_roundtrip_ok() {
+        let corpus = [
+            "urn:example:resource",
+            "urn:EXAMPLE:a:b:c/d/e?+r?=q#f",
+            "urn:example:foo%20bar/a%2Fb?k=v%20w#x%20y",
+            "urn:uuid:f81d4fae-7dec-11d0-a765-00a0c91e6bf6",
+            "urn:example:resource?a b",
+            "urn:example:resource?a[]=1",
+            "urn:example:resource?a=b|c",
+            "urn:example:resource?100%",
+            // Invalid inputs
+            "",
+            "urn:",
+            "urn:example:",
+            "urn:example:bad%zz",
+            "urn:example:resource#bad fragment",
+            "http://example.com",
+        ];
+        for input in corpus {
+            assert!(roundtrip_ok(input), "{input}");
+        }
     }
 
     #[test]
-    fn test_without_fragment() {
-        let urn = Urn::from_str("urn:example:resource#section1").unwrap();
-        let without_fragment = urn.without_fragment();
-        assert_eq!(without_fragment.fragment(), None);
+    fn test_try_from() {
+        let from_str = Urn::try_from("urn:example:resource/path").unwrap();
+        let from_string = Urn::try_from(String::from("urn:example:resource/path")).unwrap();
+        assert_eq!(from_str, from_string);
+        assert_eq!(from_str.path(), Some("path"));
+
+        assert!(matches!(Urn::try_from("example:resource"), Err(UrnFormatError::UrnSchemeExpected)));
+        assert!(matches!(Urn::try_from(String::from("urn:")), Err(UrnFormatError::InvalidUrn)));
+
+        // Usable through generic `TryInto` bounds
+        fn parse<S: TryInto<Urn, Error = UrnFormatError>>(s: S) -> Result<Urn, UrnFormatError> {
+            s.try_into()
+        }
+        assert_eq!(parse("urn:example:x").unwrap().nss(), "x");
     }
 
     #[test]
-    fn test_parse_query() {
-        let urn = Urn::from_str("urn:example:resource?key1=value1&key2=value2").unwrap();
-        let query_map = urn.parse_query().unwrap();
-        assert_eq!(query_map.get("key1"), Some(&"value1".to_string()));
-        assert_eq!(query_map.get("key2"), Some(&"value2".to_string()));
+    fn test_oid_arcs() {
+        let urn = Urn::from_str("urn:oid:2.16.840.1.113883").unwrap();
+        assert_eq!(urn.oid_arcs().unwrap().unwrap(), [2, 16, 840, 1, 113883]);
+        let urn = Urn::from_str("urn:OID:0").unwrap();
+        assert_eq!(urn.oid_arcs().unwrap().unwrap(), [0]);
+
+        assert!(Urn::from_str("urn:example:1.3.6").unwrap().oid_arcs().is_none());
+
+        for nss in ["1.a.3", "1..3", "1.03", "1.99999999999999999999"] {
+            let urn = Urn::from_str(&format!("urn:oid:{nss}")).unwrap();
+            assert!(matches!(urn.oid_arcs(), Some(Err(UrnError::Validation(_)))), "{nss}");
+        }
     }
 
     #[test]
-    fn test_is_lexically_equivalent() {
-        let urn1 = Urn::from_str("urn:EXAMPLE:resource").unwrap();
-        let urn2 = Urn::from_str("urn:example:resource").unwrap();
-        let urn3 = Urn::from_str("urn:example:resource?query=value#fragment").unwrap();
-        let urn4 = Urn::from_str("urn:example:different").unwrap();
+    fn test_path_segments() {
+        let urn = Urn::from_str("urn:example:x/path/to//resource/").unwrap();
+        let segments: Vec<_> = urn.path_segments().unwrap().collect();
+        assert_eq!(segments, ["path", "to", "resource"]);
 
-        assert!(urn1.is_lexically_equivalent(&urn2));
-        assert!(urn1.is_lexically_equivalent(&urn3)); // query and fragment don't affect lexical equivalence
-        assert!(!urn1.is_lexically_equivalent(&urn4));
+        assert!(Urn::from_str("urn:example:x").unwrap().path_segments().is_none());
+        let empty = Urn::from_str("urn:example:x/").unwrap();
+        assert_eq!(empty.path_segments().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_push_segment() {
+        let urn = Urn::from_str("urn:example:x/path/?k=v#f").unwrap();
+        let pushed = urn.push_segment("a b/c%d");
+        assert_eq!(pushed.to_string(), "urn:example:x/path/a%20b%2Fc%25d?k=v#f");
+
+        let decoded: Vec<_> = pushed.path_segments_decoded().unwrap().collect();
+        assert_eq!(decoded, ["path", "a b/c%d"]);
+        assert_eq!(Urn::from_str(&pushed.to_string()).unwrap(), pushed);
+
+        let no_path = Urn::from_str("urn:example:x").unwrap();
+        assert_eq!(no_path.push_segment("first").push_segment("second").to_string(), "urn:example:x/first/second");
+        assert_eq!(Urn::from_str("urn:example:x/").unwrap().push_segment("a").path(), Some("a"));
+    }
+
+    #[test]
+    fn test_builder_validates_nid() {
+        let build = |nid: &str| Urn::builder().nid(nid).nss("resource").build();
+        let rejected = |nid: &str| matches!(build(nid), Err(UrnBuilderError::ValidationError(_)));
+
+        assert!(build("example").is_ok());
+        assert!(build("ex-ample-2").is_ok());
+        assert!(build(&"a".repeat(32)).is_ok());
+
+        assert!(rejected(&"a".repeat(33)));
+        assert!(rejected("-example"));
+        assert!(rejected("example-"));
+        assert!(rejected(""));
+        assert!(rejected("ex ample"));
+        assert!(rejected("ex:ample"));
+
+        let err = Urn::builder().nid("ex ample").nss("resource").try_build().unwrap_err();
+        assert!(err.to_string().contains("invalid NID `ex ample` at position 2"));
+
+        // Lenient mode builds anything
+        assert!(Urn::builder().nid("ex ample").nss("resource").strict(false).build().is_ok());
+    }
+
+    #[test]
+    fn test_query_pairs() {
+        let urn = Urn::from_str("urn:example:resource?a=1&a=2&flag&b=x+y&c=").unwrap();
+        let pairs = urn.query_pairs();
+        let expected = [("a", "1"), ("a", "2"), ("flag", ""), ("b", "x y"), ("c", "")];
+        assert_eq!(pairs.len(), expected.len());
+        for ((key, value), (expected_key, expected_value)) in pairs.iter().zip(expected) {
+            assert_eq!((key.as_str(), value.as_str()), (expected_key, expected_value));
+        }
+
+        // The map keeps the last value only
+        assert_eq!(urn.parse_query().unwrap()["a"], "2");
+
+        assert!(Urn::from_str("urn:example:resource?").unwrap().query_pairs().is_empty());
+        assert!(Urn::from_str("urn:example:resource").unwrap().query_pairs().is_empty());
+    }
+
+    #[test]
+    // The normalized query must keep its exact encoding
+    #[allow(deprecated)]
+    fn test_normalize_percent_encoding() {
+        let urn = Urn::from_str("urn:Example:Caf%c3%A9-x/Dir%2fsub/%aB?Key=%3d%3Dv#Frag%7e").unwrap();
+        let normalized = urn.normalize_percent_encoding();
+        assert_eq!(normalized.nid(), "Example");
+        assert_eq!(normalized.nss(), "Caf%C3%A9-x");
+        assert_eq!(normalized.path(), Some("Dir%2Fsub/%AB"));
+        assert_eq!(normalized.query(), Some("Key=%3D%3Dv"));
+        assert_eq!(normalized.fragment(), Some("Frag%7E"));
+
+        // Already normalized URNs are left unchanged
+        assert_eq!(normalized.normalize_percent_encoding().to_string(), normalized.to_string());
+        let plain = Urn::from_str("urn:example:MixedCase/Path?Q=V").unwrap();
+        assert_eq!(plain.normalize_percent_encoding().to_string(), plain.to_string());
+    }
+
+    #[test]
+    fn test_uppercase_percent_encoding_ignores_incomplete_triplets() {
+        assert_eq!(uppercase_percent_encoding("a%2fb%"), "a%2Fb%");
+        assert_eq!(uppercase_percent_encoding("a%zzb%f"), "a%zzb%f");
+        assert!(matches!(uppercase_percent_encoding("a%2Fb"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_scheme_is_case_insensitive() {
+        for input in ["URN:example:foo", "Urn:example:foo", "uRn:example:foo/bar?k=v"] {
+            let urn = Urn::from_str(input).unwrap();
+            assert_eq!(urn.nid(), "example");
+            assert_eq!(urn.nss(), "foo");
+            assert!(urn.to_string().starts_with("urn:example:foo"));
+            assert_eq!(urn, Urn::from_str(&input.to_lowercase()).unwrap());
+        }
+        assert!(Urn::from_str_fast("URN:example:foo", "example").is_ok());
+    }
+
+    #[test]
+    fn test_scheme_lookalikes_are_rejected() {
+        for input in ["turn:example:foo", "urn", "UR:example:foo", "urnx:example:foo"] {
+            assert!(matches!(Urn::from_str(input), Err(UrnFormatError::UrnSchemeExpected)), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_resolve_via() {
+        let base = Url::parse("https://gw.example/urn/").unwrap();
+
+        let urn = Urn::from_str("urn:Example:doc").unwrap();
+        assert_eq!(urn.resolve_via(&base).unwrap().as_str(), "https://gw.example/urn/example/doc");
+
+        let urn = Urn::from_str("urn:example:doc/1/2?k=v#sec").unwrap();
+        assert_eq!(urn.resolve_via(&base).unwrap().as_str(), "https://gw.example/urn/example/doc/1/2?k=v#sec");
+
+        // The trailing slash of the base is optional, and its query is replaced
+        let base = Url::parse("https://gw.example/urn?token=x").unwrap();
+        let urn = Urn::from_str("urn:example:doc/1").unwrap();
+        assert_eq!(urn.resolve_via(&base).unwrap().as_str(), "https://gw.example/urn/example/doc/1");
+
+        // Cannot-be-a-base URLs have no path to append to
+        for base in ["mailto:gw@example.com", "data:text/plain,urn"] {
+            assert_eq!(urn.resolve_via(&Url::parse(base).unwrap()), None, "{base}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_via_keeps_segments() {
+        let base = Url::parse("https://gw.example/").unwrap();
+
+        // Encoded slashes stay within their segment
+        let urn = Urn::from_str("urn:example:a%2Fb/c%2fd").unwrap();
+        assert_eq!(urn.resolve_via(&base).unwrap().as_str(), "https://gw.example/example/a%2Fb/c%2Fd");
+
+        // Dot segments can't climb above the base
+        let urn = Urn::builder().nid("example").nss("doc").path("../../admin").build().unwrap();
+        assert_eq!(urn.resolve_via(&base.join("urn/").unwrap()).unwrap().as_str(), "https://gw.example/urn/example/doc/admin");
+    }
+
+    #[test]
+    fn test_validate_all() {
+        assert!(Urn::from_str("urn:example:resource/a/b?k=v#top").unwrap().validate_all().is_empty());
+
+        // The builder encodes illegal characters, so these URNs are assembled by hand
+        let unchecked = |nid: &str, nss: &str, path: Option<&str>, fragment: Option<&str>| Urn {
+            nid: Arc::from(nid),
+            nss: Arc::from(nss),
+            path: path.map(Arc::from),
+            query: None,
+            fragment: fragment.map(Arc::from),
+            raw: None,
+            builder_settings: BuilderOnly,
+        };
+
+        let urn = unchecked("-example", "", Some("a b"), None);
+        assert_eq!(urn.validate_all(), vec![
+            UrnValidationError::IllegalCharacter { component: UrnComponent::Nid, position: 0 },
+            UrnValidationError::Empty { component: UrnComponent::Nss },
+            UrnValidationError::IllegalCharacter { component: UrnComponent::Path, position: 1 },
+        ]);
+
+        let urn = unchecked("", "res%2", None, Some("a<b"));
+        assert_eq!(urn.validate_all(), vec![
+            UrnValidationError::Empty { component: UrnComponent::Nid },
+            UrnValidationError::IllegalCharacter { component: UrnComponent::Nss, position: 3 },
+            UrnValidationError::IllegalCharacter { component: UrnComponent::Fragment, position: 1 },
+        ]);
+
+        let urn = Urn::builder().nid("a".repeat(33)).nss("resource").strict(false).build().unwrap();
+        assert_eq!(
+            urn.validate_all(),
+            vec![UrnValidationError::InvalidLength { component: UrnComponent::Nid, len: 33 }]
+        );
+    }
+
+    #[test]
+    fn test_builder_max_path_depth() {
+        let mut builder = Urn::builder();
+        builder.nid("example").nss("resource").max_path_depth(3);
+
+        // Exactly at the limit
+        let urn = builder.path("a/b/c").build().unwrap();
+        assert_eq!(urn.path(), Some("a/b/c"));
+        assert!(builder.try_build().is_ok());
+
+        // One segment over
+        builder.path("a/b/c/d");
+        assert!(matches!(
+            builder.build(),
+            Err(UrnBuilderError::ValidationError(message)) if message == "path depth 4 exceeds the maximum of 3"
+        ));
+        assert!(matches!(builder.try_build(), Err(UrnError::PathTooDeep { depth: 4, max: 3 })));
+
+        // Empty segments don't count, and the check also applies to lenient builders
+        assert!(builder.path("a//b/c/").build().is_ok());
+        assert!(builder.path("a/b/c/d").strict(false).build().is_err());
+    }
+
+    #[test]
+    fn test_builder_max_path_depth_without_path() {
+        let urn = Urn::builder().nid("example").nss("resource").max_path_depth(0).try_build();
+        assert!(urn.is_ok());
+        assert!(matches!(
+            Urn::builder().nid("example").nss("resource").path("a").max_path_depth(0).try_build(),
+            Err(UrnError::PathTooDeep { depth: 1, max: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_fragment_params() {
+        let pairs = |urn: &str| Urn::from_str(urn).unwrap().fragment_params();
+        let owned = |pairs: &[(&str, &str)]| {
+            pairs.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect::<Vec<_>>()
+        };
+
+        assert_eq!(pairs("urn:example:video#t=10,20"), Some(owned(&[("t", "10,20")])));
+        assert_eq!(pairs("urn:example:doc#section1"), Some(owned(&[("section1", "")])));
+        assert_eq!(pairs("urn:example:doc"), None);
+        assert_eq!(pairs("urn:example:doc#"), Some(vec![]));
+
+        assert_eq!(
+            pairs("urn:example:video#t=npt:10;track=a%20b&&id=x+y"),
+            Some(owned(&[("t", "npt:10"), ("track", "a b"), ("id", "x+y")]))
+        );
     }
 }